@@ -18,10 +18,33 @@ use crate::util::{
     TEXT_COLOR,
     BACKGROUND_COLOR,
     MainMenuButtonAction,
+    Focusable,
+    FocusOrder,
     OnGamesMenuScreen,
     GameMenuButtonAction,
+    OnMinesweeperOptionsScreen,
+    OptionsMenuButtonAction,
+    OnMinesweeperStatsScreen,
+    StatsMenuButtonAction,
+    DifficultyButton,
+    TopologyButton,
+    CustomBoardField,
+    CustomBoardButton,
+    CustomBoardText,
+    LivesButton,
+    LivesText,
+    OptionsErrorText,
+    OptionToggle,
+    OptionToggleButton,
+    OnTronOptionsScreen,
+    TronMenuButtonAction,
+    CycleSlotButton,
+    TronRoundsButton,
+    TronRoundsText,
 };
 
+use arcade_util::{Difficulty, MinesweeperStats, MinesweeperReplay, Topology, MAX_CYCLES, save_path};
+
 pub fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     let font_asset = asset_server.load("fonts/FiraSans-Bold.ttf");
     // Common style for all buttons on the screen
@@ -74,17 +97,18 @@ pub fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
             );
 
             // Three buttons: New Game, Game List, Quit
-            for (action, text) in [ // Here are all the buttons iterated
+            for (index, (action, text)) in [ // Here are all the buttons iterated
                 (MainMenuButtonAction::Play, "Play Snake"),
                 (MainMenuButtonAction::GameList, "Other Games"),
                 (MainMenuButtonAction::Quit, "Quit"),
-            ] {
+            ].into_iter().enumerate() {
                 parent.spawn(ButtonBundle {
                     style: button_style.clone(),
                     background_color: NORMAL_BUTTON_COLOR.into(),
                     ..default()
                 })
                 .insert(action)
+                .insert(Focusable(index))
                 .with_children(|parent| {
                     parent.spawn(TextBundle::from_section(
                         text,
@@ -99,8 +123,8 @@ pub fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
 pub fn game_list_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     let font_asset = asset_server.load("fonts/FiraSans-Bold.ttf");
     let button_style = Style {
-        size: Size::new(Val::Px(250.0), Val::Px(65.0)),
-        margin: UiRect::all(Val::Px(20.0)),
+        size: Size::new(Val::Px(400.0), Val::Px(65.0)),
+        margin: UiRect::all(Val::Px(15.0)),
         justify_content: JustifyContent::Center,
         align_items: AlignItems::Center,
         ..default()
@@ -133,17 +157,23 @@ pub fn game_list_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         })
         .with_children(|parent| {
             // X buttons: Snake, Back
-            for (action, text) in [ // Here are all the buttons iterated
+            for (index, (action, text)) in [ // Here are all the buttons iterated
                 (GameMenuButtonAction::PlaySnake, "Snake"),
                 (GameMenuButtonAction::PlayMinesweeper, "Minesweeper"),
+                (GameMenuButtonAction::PlayMinesweeperVersus, "Minesweeper Versus"),
+                (GameMenuButtonAction::PlayTron, "Tron"),
+                (GameMenuButtonAction::TronOptions, "Tron Options"),
+                (GameMenuButtonAction::MinesweeperOptions, "Minesweeper Options"),
+                (GameMenuButtonAction::MinesweeperStats, "Minesweeper Stats"),
                 (GameMenuButtonAction::BackToMainMenu, "Back"),
-            ] {
+            ].into_iter().enumerate() {
                 parent.spawn(ButtonBundle {
                     style: button_style.clone(),
                     background_color: NORMAL_BUTTON_COLOR.into(),
                     ..default()
                 })
                 .insert(action)
+                .insert(Focusable(index))
                 .with_children(|parent| {
                     parent.spawn(TextBundle::from_section(
                         text,
@@ -154,3 +184,513 @@ pub fn game_list_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         });
     });
 }
+
+pub fn minesweeper_options_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font_asset = asset_server.load("fonts/FiraSans-Bold.ttf");
+    // The buttons are focused in the order they are spawned, row by row
+    let mut focus_order = FocusOrder::default();
+    let button_style = Style {
+        size: Size::new(Val::Px(220.0), Val::Px(65.0)),
+        margin: UiRect::all(Val::Px(10.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let small_button_style = Style {
+        size: Size::new(Val::Px(65.0), Val::Px(65.0)),
+        ..button_style.clone()
+    };
+    let button_text_style = TextStyle {
+        font_size: 30.0,
+        color: TEXT_COLOR,
+        font: font_asset.clone(),
+    };
+
+    commands.spawn(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        ..default()
+    })
+    .insert(OnMinesweeperOptionsScreen)
+    .with_children(|parent| {
+        parent.spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: BACKGROUND_COLOR.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Minesweeper",
+                TextStyle {
+                    font_size: 60.0,
+                    font: font_asset.clone(),
+                    color: TEXT_COLOR,
+                })
+               .with_style(Style {
+                    margin: UiRect::all(Val::Px(30.0)),
+                    ..default()
+                }),
+            );
+
+            // One button per difficulty, the selected one is marked by the difficulty systems
+            parent.spawn(NodeBundle::default())
+                .with_children(|parent| {
+                    for difficulty in [
+                        Difficulty::Beginner,
+                        Difficulty::Intermediate,
+                        Difficulty::Expert,
+                        Difficulty::Custom,
+                    ] {
+                        parent.spawn(ButtonBundle {
+                            style: button_style.clone(),
+                            background_color: NORMAL_BUTTON_COLOR.into(),
+                            ..default()
+                        })
+                        .insert(DifficultyButton(difficulty))
+                        .insert(focus_order.next())
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                difficulty.name(),
+                                button_text_style.clone(),
+                            ));
+                        });
+                    }
+                });
+
+            // One button per topology, the selected one is marked by the topology systems
+            parent.spawn(NodeBundle::default())
+                .with_children(|parent| {
+                    for topology in Topology::ALL {
+                        parent.spawn(ButtonBundle {
+                            style: button_style.clone(),
+                            background_color: NORMAL_BUTTON_COLOR.into(),
+                            ..default()
+                        })
+                        .insert(TopologyButton(topology))
+                        .insert(focus_order.next())
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                topology.name(),
+                                button_text_style.clone(),
+                            ));
+                        });
+                    }
+                });
+
+            // A row of "label - value +" for every value of the custom board
+            for (field, label) in [
+                (CustomBoardField::Width, "Width"),
+                (CustomBoardField::Height, "Height"),
+                (CustomBoardField::Mines, "Mines"),
+            ] {
+                parent.spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(label, button_text_style.clone())
+                        .with_style(Style {
+                            size: Size::new(Val::Px(120.0), Val::Auto),
+                            ..default()
+                        }));
+                    parent.spawn(ButtonBundle {
+                        style: small_button_style.clone(),
+                        background_color: NORMAL_BUTTON_COLOR.into(),
+                        ..default()
+                    })
+                    .insert(CustomBoardButton(field, -1))
+                    .insert(focus_order.next())
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section("-", button_text_style.clone()));
+                    });
+                    parent.spawn(TextBundle::from_section("", button_text_style.clone())
+                        .with_style(Style {
+                            size: Size::new(Val::Px(80.0), Val::Auto),
+                            justify_content: JustifyContent::Center,
+                            ..default()
+                        }))
+                        .insert(CustomBoardText(field));
+                    parent.spawn(ButtonBundle {
+                        style: small_button_style.clone(),
+                        background_color: NORMAL_BUTTON_COLOR.into(),
+                        ..default()
+                    })
+                    .insert(CustomBoardButton(field, 1))
+                    .insert(focus_order.next())
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section("+", button_text_style.clone()));
+                    });
+                });
+            }
+
+            // The lives in the same "label - value +" form
+            parent.spawn(NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section("Lives", button_text_style.clone())
+                    .with_style(Style {
+                        size: Size::new(Val::Px(120.0), Val::Auto),
+                        ..default()
+                    }));
+                parent.spawn(ButtonBundle {
+                    style: small_button_style.clone(),
+                    background_color: NORMAL_BUTTON_COLOR.into(),
+                    ..default()
+                })
+                .insert(LivesButton(-1))
+                .insert(focus_order.next())
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section("-", button_text_style.clone()));
+                });
+                parent.spawn(TextBundle::from_section("", button_text_style.clone())
+                    .with_style(Style {
+                        size: Size::new(Val::Px(80.0), Val::Auto),
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    }))
+                    .insert(LivesText);
+                parent.spawn(ButtonBundle {
+                    style: small_button_style.clone(),
+                    background_color: NORMAL_BUTTON_COLOR.into(),
+                    ..default()
+                })
+                .insert(LivesButton(1))
+                .insert(focus_order.next())
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section("+", button_text_style.clone()));
+                });
+            });
+
+            // Toggles for the on/off settings, the text is set by the option toggle systems
+            for option in [
+                OptionToggle::QuestionMarks,
+                OptionToggle::NoGuess,
+                OptionToggle::Practice,
+                OptionToggle::Endless,
+            ] {
+                parent.spawn(ButtonBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(400.0), Val::Px(65.0)),
+                        ..button_style.clone()
+                    },
+                    background_color: NORMAL_BUTTON_COLOR.into(),
+                    ..default()
+                })
+                .insert(OptionToggleButton(option))
+                .insert(focus_order.next())
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section("", button_text_style.clone()));
+                });
+            }
+
+            parent.spawn(TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 20.0,
+                    font: font_asset.clone(),
+                    color: TEXT_COLOR,
+                }))
+                .insert(OptionsErrorText);
+
+            parent.spawn(ButtonBundle {
+                style: button_style.clone(),
+                background_color: NORMAL_BUTTON_COLOR.into(),
+                ..default()
+            })
+            .insert(OptionsMenuButtonAction::BackToGameList)
+            .insert(focus_order.next())
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section("Back", button_text_style.clone()));
+            });
+        });
+    });
+}
+
+pub fn minesweeper_stats_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font_asset = asset_server.load("fonts/FiraSans-Bold.ttf");
+    // The buttons are focused in the order they are spawned, row by row
+    let mut focus_order = FocusOrder::default();
+    let button_style = Style {
+        size: Size::new(Val::Px(220.0), Val::Px(65.0)),
+        margin: UiRect::all(Val::Px(20.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font_size: 30.0,
+        color: TEXT_COLOR,
+        font: font_asset.clone(),
+    };
+    let cell_text_style = TextStyle {
+        font_size: 24.0,
+        color: TEXT_COLOR,
+        font: font_asset.clone(),
+    };
+
+    let stats = MinesweeperStats::load().unwrap_or_else(|e| {
+        warn!("Could not load the minesweeper stats: {}", e);
+        MinesweeperStats::default()
+    });
+
+    // One row per board that has been played, below a row with the column names
+    let mut rows = vec![[
+        "Board", "Played", "Won", "Streak", "Best streak", "Best time", "Best 3BV/s", "Best eff.",
+        "Best score",
+    ].map(str::to_string)];
+    for (board, board_stats) in stats.boards() {
+        let best = |value: Option<f32>, precision: usize, unit: &str| value
+            .map_or("-".to_string(), |value| format!("{:.*}{}", precision, value, unit));
+        rows.push([
+            board.clone(),
+            board_stats.played.to_string(),
+            format!("{:.0}%", board_stats.win_rate()),
+            board_stats.streak.to_string(),
+            board_stats.best_streak.to_string(),
+            best(board_stats.best_time, 1, " s"),
+            best(board_stats.best_three_bv_per_second, 2, ""),
+            best(board_stats.best_efficiency, 0, "%"),
+            board_stats.best_score.map_or("-".to_string(), |score| score.to_string()),
+        ]);
+    }
+
+    // Only the replays that have been saved can be watched
+    let last_game = (MinesweeperReplay::LastGame, "Watch last game".to_string());
+    let replays: Vec<(MinesweeperReplay, String)> = std::iter::once(last_game)
+        .chain(stats.boards().map(|(board, _)| (
+            MinesweeperReplay::BestTime(board.clone()),
+            format!("Best {}", board),
+        )))
+        .filter(|(replay, _)| save_path("minesweeper", &replay.file_name())
+            .is_ok_and(|path| path.exists()))
+        .collect();
+
+    commands.spawn(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        ..default()
+    })
+    .insert(OnMinesweeperStatsScreen)
+    .with_children(|parent| {
+        parent.spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(20.0)),
+                ..default()
+            },
+            background_color: BACKGROUND_COLOR.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Minesweeper Stats",
+                TextStyle {
+                    font_size: 60.0,
+                    font: font_asset.clone(),
+                    color: TEXT_COLOR,
+                })
+               .with_style(Style {
+                    margin: UiRect::all(Val::Px(30.0)),
+                    ..default()
+                }),
+            );
+
+            if rows.len() == 1 {
+                parent.spawn(TextBundle::from_section(
+                    "No games of minesweeper finished yet",
+                    cell_text_style.clone(),
+                ));
+            } else {
+                for row in rows.iter() {
+                    parent.spawn(NodeBundle::default())
+                        .with_children(|parent| {
+                            for (column, cell) in row.iter().enumerate() {
+                                // The board names are wider than the numbers
+                                let width = if column == 0 { 200.0 } else { 120.0 };
+                                parent.spawn(TextBundle::from_section(cell, cell_text_style.clone())
+                                    .with_style(Style {
+                                        size: Size::new(Val::Px(width), Val::Auto),
+                                        margin: UiRect::vertical(Val::Px(4.0)),
+                                        ..default()
+                                    }));
+                            }
+                        });
+                }
+            }
+
+            parent.spawn(NodeBundle::default())
+                .with_children(|parent| {
+                    for (replay, label) in replays {
+                        parent.spawn(ButtonBundle {
+                            style: button_style.clone(),
+                            background_color: NORMAL_BUTTON_COLOR.into(),
+                            ..default()
+                        })
+                        .insert(StatsMenuButtonAction::WatchReplay(replay))
+                        .insert(focus_order.next())
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(label, cell_text_style.clone()));
+                        });
+                    }
+                });
+
+            parent.spawn(ButtonBundle {
+                style: button_style.clone(),
+                background_color: NORMAL_BUTTON_COLOR.into(),
+                ..default()
+            })
+            .insert(StatsMenuButtonAction::BackToGameList)
+            .insert(focus_order.next())
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section("Back", button_text_style.clone()));
+            });
+        });
+    });
+}
+
+pub fn tron_options_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font_asset = asset_server.load("fonts/FiraSans-Bold.ttf");
+    // The buttons are focused in the order they are spawned, row by row
+    let mut focus_order = FocusOrder::default();
+    let button_style = Style {
+        size: Size::new(Val::Px(300.0), Val::Px(65.0)),
+        margin: UiRect::all(Val::Px(10.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let small_button_style = Style {
+        size: Size::new(Val::Px(65.0), Val::Px(65.0)),
+        ..button_style.clone()
+    };
+    let button_text_style = TextStyle {
+        font_size: 30.0,
+        color: TEXT_COLOR,
+        font: font_asset.clone(),
+    };
+
+    commands.spawn(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        ..default()
+    })
+    .insert(OnTronOptionsScreen)
+    .with_children(|parent| {
+        parent.spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: BACKGROUND_COLOR.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Tron",
+                TextStyle {
+                    font_size: 60.0,
+                    font: font_asset.clone(),
+                    color: TEXT_COLOR,
+                })
+               .with_style(Style {
+                    margin: UiRect::all(Val::Px(30.0)),
+                    ..default()
+                }),
+            );
+
+            // One button per starting position, the text is set by the cycle slot systems
+            for slot in 0..MAX_CYCLES {
+                parent.spawn(ButtonBundle {
+                    style: button_style.clone(),
+                    background_color: NORMAL_BUTTON_COLOR.into(),
+                    ..default()
+                })
+                .insert(CycleSlotButton(slot))
+                .insert(focus_order.next())
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section("", button_text_style.clone()));
+                });
+            }
+
+            // The rounds in the same "label - value +" form as the minesweeper options
+            parent.spawn(NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section("Best of", button_text_style.clone())
+                    .with_style(Style {
+                        size: Size::new(Val::Px(120.0), Val::Auto),
+                        ..default()
+                    }));
+                parent.spawn(ButtonBundle {
+                    style: small_button_style.clone(),
+                    background_color: NORMAL_BUTTON_COLOR.into(),
+                    ..default()
+                })
+                .insert(TronRoundsButton(-1))
+                .insert(focus_order.next())
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section("-", button_text_style.clone()));
+                });
+                parent.spawn(TextBundle::from_section("", button_text_style.clone())
+                    .with_style(Style {
+                        size: Size::new(Val::Px(80.0), Val::Auto),
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    }))
+                    .insert(TronRoundsText);
+                parent.spawn(ButtonBundle {
+                    style: small_button_style.clone(),
+                    background_color: NORMAL_BUTTON_COLOR.into(),
+                    ..default()
+                })
+                .insert(TronRoundsButton(1))
+                .insert(focus_order.next())
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section("+", button_text_style.clone()));
+                });
+            });
+
+            parent.spawn(ButtonBundle {
+                style: button_style.clone(),
+                background_color: NORMAL_BUTTON_COLOR.into(),
+                ..default()
+            })
+            .insert(TronMenuButtonAction::BackToGameList)
+            .insert(focus_order.next())
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section("Back", button_text_style.clone()));
+            });
+        });
+    });
+}
@@ -6,21 +6,70 @@ use bevy::prelude::{
     NextState,
     IntoSystemAppConfig,
     OnExit,
+    IntoSystemConfig,
     IntoSystemConfigs,
     OnUpdate
 };
 
-use arcade_util::{ActiveGameState, ArcadeState, despawn_component};
+use arcade_util::{
+    ActiveGameState,
+    ArcadeState,
+    MinesweeperSettings,
+    MinesweeperReplay,
+    TronSettings,
+    despawn_component,
+};
 
 use crate::{
-    util::{MenuState, OnMainMenuScreen, OnGamesMenuScreen},
-    menu::{main_menu_setup, game_list_setup},
-    systems::{menu_action, game_list_action, button_system, keybinding_system, text_update_system}
+    util::{
+        MenuState,
+        OnMainMenuScreen,
+        OnGamesMenuScreen,
+        OnMinesweeperOptionsScreen,
+        OnMinesweeperStatsScreen,
+        OnTronOptionsScreen,
+        ActivateFocused,
+    },
+    menu::{
+        main_menu_setup,
+        game_list_setup,
+        minesweeper_options_setup,
+        minesweeper_stats_setup,
+        tron_options_setup,
+    },
+    systems::{
+        menu_action,
+        game_list_action,
+        options_action,
+        stats_action,
+        button_system,
+        focus_system,
+        keybinding_system,
+        text_update_system,
+        difficulty_button_system,
+        difficulty_selection_system,
+        topology_button_system,
+        topology_selection_system,
+        custom_board_button_system,
+        custom_board_text_system,
+        lives_button_system,
+        lives_text_system,
+        option_toggle_button_system,
+        option_toggle_text_system,
+        tron_options_action,
+        cycle_slot_button_system,
+        cycle_slot_text_system,
+        tron_rounds_button_system,
+        tron_rounds_text_system,
+    }
 };
 
 // This plugin manages the menu, with 2 different screens:
 // - a main menu with "Play *ActiveGamEState*", "More Games", "Quit"
 // - A game list menu with a list of games to choose from
+// - A minesweeper options menu to choose the difficulty and topology
+// - A minesweeper stats menu with the results per board
+// - A tron options menu to choose the cycles and the number of rounds
 pub struct MenuPlugin;
 
 impl Plugin for MenuPlugin {
@@ -31,6 +80,10 @@ impl Plugin for MenuPlugin {
             // Current screen in the menu is handled by an independent state from `ArcadeState`
             .add_state::<MenuState>()
             .add_state::<ActiveGameState>()
+            .add_event::<ActivateFocused>()
+            .init_resource::<MinesweeperSettings>()
+            .init_resource::<MinesweeperReplay>()
+            .init_resource::<TronSettings>()
             // Systems to handle the main menu screen
             .add_system(menu_setup.in_schedule(OnEnter(ArcadeState::Menu)))
             .add_system(main_menu_setup.in_schedule(OnEnter(MenuState::Main)))
@@ -38,11 +91,57 @@ impl Plugin for MenuPlugin {
             // Systems to handle the game list menu screen
             .add_system(game_list_setup.in_schedule(OnEnter(MenuState::GameSelection)))
             .add_system(despawn_component::<OnGamesMenuScreen>.in_schedule(OnExit(MenuState::GameSelection)))
-            // Common systems to all screens that handles buttons behavior
+            // Systems to handle the minesweeper options screen
+            .add_system(minesweeper_options_setup.in_schedule(OnEnter(MenuState::MinesweeperOptions)))
+            .add_system(despawn_component::<OnMinesweeperOptionsScreen>.in_schedule(OnExit(MenuState::MinesweeperOptions)))
+            .add_systems(
+                (
+                    options_action,
+                    difficulty_button_system,
+                    difficulty_selection_system,
+                    topology_button_system,
+                    topology_selection_system,
+                    custom_board_button_system,
+                    custom_board_text_system,
+                    lives_button_system,
+                    lives_text_system,
+                    option_toggle_button_system,
+                    option_toggle_text_system,
+                )
+                .after(focus_system)
+                .in_set(OnUpdate(MenuState::MinesweeperOptions)),
+            )
+            // Systems to handle the minesweeper stats screen
+            .add_system(minesweeper_stats_setup.in_schedule(OnEnter(MenuState::MinesweeperStats)))
+            .add_system(despawn_component::<OnMinesweeperStatsScreen>.in_schedule(OnExit(MenuState::MinesweeperStats)))
+            .add_system(stats_action.after(focus_system).in_set(OnUpdate(MenuState::MinesweeperStats)))
+            // Systems to handle the tron options screen
+            .add_system(tron_options_setup.in_schedule(OnEnter(MenuState::TronOptions)))
+            .add_system(despawn_component::<OnTronOptionsScreen>.in_schedule(OnExit(MenuState::TronOptions)))
+            .add_systems(
+                (
+                    tron_options_action,
+                    cycle_slot_button_system,
+                    cycle_slot_text_system,
+                    tron_rounds_button_system,
+                    tron_rounds_text_system,
+                )
+                .after(focus_system)
+                .in_set(OnUpdate(MenuState::TronOptions)),
+            )
+            // Common systems to all screens that handles buttons behavior. A button is only
+            // activated in the frame the focus system sends it, while its screen is still shown
             .add_systems(
                 (
+                    focus_system,
                     menu_action,
                     game_list_action,
+                )
+                .chain()
+                .in_set(OnUpdate(ArcadeState::Menu)),
+            )
+            .add_systems(
+                (
                     button_system,
                     keybinding_system,
                     text_update_system,
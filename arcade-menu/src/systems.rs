@@ -1,12 +1,25 @@
 use bevy::{
     prelude::*,
-    app::AppExit
+    app::AppExit,
+    ecs::system::SystemParam,
 };
 
-use arcade_util::{ArcadeState, ActiveGameState};
+use arcade_util::{
+    ArcadeState,
+    ActiveGameState,
+    MinesweeperSettings,
+    MinesweeperReplay,
+    Difficulty,
+    CustomBoard,
+    MAX_LIVES,
+    TronSettings,
+};
 
 use crate::util::{
     SelectedOption,
+    Focusable,
+    Focused,
+    ActivateFocused,
     PRESSED_BUTTON_COLOR,
     HOVERED_PRESSED_BUTTON_COLOR,
     HOVERED_BUTTON_COLOR,
@@ -14,53 +27,174 @@ use crate::util::{
     MenuState,
     MainMenuButtonAction,
     GameMenuButtonAction,
+    OptionsMenuButtonAction,
+    StatsMenuButtonAction,
+    DifficultyButton,
+    TopologyButton,
+    CustomBoardField,
+    CustomBoardButton,
+    CustomBoardText,
+    LivesButton,
+    LivesText,
+    OptionsErrorText,
+    OptionToggleButton,
+    TronMenuButtonAction,
+    CycleSlotButton,
+    TronRoundsButton,
+    TronRoundsText,
 };
 
-type MouseColorInteraction<'a> = (&'a Interaction, &'a mut BackgroundColor, Option<&'a SelectedOption>);
-type MouseMainMenuInteraction<'a> = (&'a Interaction, &'a MainMenuButtonAction);
-type MouseGameMenuInteraction<'a> = (&'a Interaction, &'a GameMenuButtonAction);
+type MouseColorInteraction<'a> = (
+    &'a Interaction,
+    &'a mut BackgroundColor,
+    Option<&'a SelectedOption>,
+    Option<&'a Focused>,
+);
+type MouseMainMenuInteraction<'a> = (&'a Interaction, &'a MainMenuButtonAction, Option<&'a Focused>);
+type MouseGameMenuInteraction<'a> = (&'a Interaction, &'a GameMenuButtonAction, Option<&'a Focused>);
+type MouseOptionsMenuInteraction<'a> = (&'a Interaction, &'a OptionsMenuButtonAction, Option<&'a Focused>);
+type MouseStatsMenuInteraction<'a> = (&'a Interaction, &'a StatsMenuButtonAction, Option<&'a Focused>);
+type MouseTronMenuInteraction<'a> = (&'a Interaction, &'a TronMenuButtonAction, Option<&'a Focused>);
+type FocusableButton<'a> = (Entity, &'a Focusable, Ref<'a, Interaction>, Option<&'a Focused>);
+// A button changing a setting, which is pressed once per click rather than for as long as it is
+// held down
+type SettingButton<'a, T> = (Ref<'a, Interaction>, &'a T, Option<&'a Focused>);
+
+// The keys that move the focus back or forward, next to Tab and shift Tab
+const FOCUS_BACK_KEYS: [KeyCode; 4] = [KeyCode::Up, KeyCode::K, KeyCode::Left, KeyCode::H];
+const FOCUS_FORWARD_KEYS: [KeyCode; 4] = [KeyCode::Down, KeyCode::J, KeyCode::Right, KeyCode::L];
+const FOCUS_BACK_PAD: [GamepadButtonType; 2] = [GamepadButtonType::DPadUp, GamepadButtonType::DPadLeft];
+const FOCUS_FORWARD_PAD: [GamepadButtonType; 2] = [GamepadButtonType::DPadDown, GamepadButtonType::DPadRight];
+const ACTIVATE_KEYS: [KeyCode; 2] = [KeyCode::Return, KeyCode::NumpadEnter];
+const SHIFT_KEYS: [KeyCode; 2] = [KeyCode::LShift, KeyCode::RShift];
 
-// This system handles the buttons background changes
+// The keyboard and the buttons of all connected gamepads, to move around the menu
+#[derive(SystemParam)]
+pub struct MenuInput<'w> {
+    keys: Res<'w, Input<KeyCode>>,
+    gamepads: Res<'w, Gamepads>,
+    gamepad_buttons: Res<'w, Input<GamepadButton>>,
+}
+
+impl MenuInput<'_> {
+    // Whether any of the keys, or any of the buttons on any gamepad, was pressed this frame
+    pub fn just_pressed(&self, keys: &[KeyCode], buttons: &[GamepadButtonType]) -> bool {
+        self.keys.any_just_pressed(keys.iter().copied())
+            || self.gamepads.iter().any(|gamepad| buttons.iter()
+                .any(|button| self.gamepad_buttons.just_pressed(GamepadButton::new(gamepad, *button))))
+    }
+
+    // The direction the focus is moved in this frame, if any
+    fn focus_step(&self) -> Option<isize> {
+        let tab = self.keys.just_pressed(KeyCode::Tab);
+        let shift = self.keys.any_pressed(SHIFT_KEYS);
+        if self.just_pressed(&FOCUS_BACK_KEYS, &FOCUS_BACK_PAD) || tab && shift {
+            Some(-1)
+        } else if self.just_pressed(&FOCUS_FORWARD_KEYS, &FOCUS_FORWARD_PAD) || tab {
+            Some(1)
+        } else {
+            None
+        }
+    }
+}
+
+// Whether a setting button was clicked this frame, or activated while it has the focus
+fn setting_pressed(
+    interaction: &Ref<Interaction>,
+    focused: Option<&Focused>,
+    activated: bool,
+) -> bool {
+    let clicked = interaction.is_changed() && **interaction == Interaction::Clicked;
+    clicked || activated && focused.is_some()
+}
+
+// This system handles the buttons background changes. A focused button looks hovered
 pub fn button_system(
     mut interaction_query: Query<MouseColorInteraction, With<Button>>,
 ) {
-    for (interaction, mut color, selected) in &mut interaction_query {
-        *color = match (*interaction, selected) {
-            (Interaction::Clicked, _) | (Interaction::None, Some(_)) => PRESSED_BUTTON_COLOR.into(),
-            (Interaction::Hovered, Some(_)) => HOVERED_PRESSED_BUTTON_COLOR.into(),
-            (Interaction::Hovered, None) => HOVERED_BUTTON_COLOR.into(),
-            (Interaction::None, None) => NORMAL_BUTTON_COLOR.into(),
+    for (interaction, mut color, selected, focused) in &mut interaction_query {
+        let hovered = *interaction == Interaction::Hovered || focused.is_some();
+        *color = match (*interaction, selected, hovered) {
+            (Interaction::Clicked, _, _) => PRESSED_BUTTON_COLOR.into(),
+            (_, Some(_), true) => HOVERED_PRESSED_BUTTON_COLOR.into(),
+            (_, Some(_), false) => PRESSED_BUTTON_COLOR.into(),
+            (_, None, true) => HOVERED_BUTTON_COLOR.into(),
+            (_, None, false) => NORMAL_BUTTON_COLOR.into(),
         }
     }
 }
 
-// Handles keybindings for the menu
-// TODO: Make this more expressive in the future
+// Moves the focus between the buttons of the screen with the arrow keys, the vim keys, Tab or the
+// d-pad of a gamepad, in a loop. Hovering a button with the mouse focuses it too. The first button
+// has the focus when a screen is opened. Enter, or the south button of a gamepad, activates the
+// focused button
+pub fn focus_system(
+    mut commands: Commands,
+    input: MenuInput,
+    buttons: Query<FocusableButton>,
+    mut activations: EventWriter<ActivateFocused>,
+) {
+    let mut order: Vec<_> = buttons.iter().collect();
+    if order.is_empty() {
+        return;
+    }
+    order.sort_by_key(|(_, focusable, _, _)| focusable.0);
+
+    let current = order.iter().position(|(_, _, _, focused)| focused.is_some());
+    let hovered = order.iter().position(|(_, _, interaction, _)| {
+        interaction.is_changed() && **interaction == Interaction::Hovered
+    });
+    let target = match (current, hovered, input.focus_step()) {
+        (_, Some(hovered), _) => hovered,
+        (Some(current), None, Some(step)) => {
+            (current as isize + step).rem_euclid(order.len() as isize) as usize
+        },
+        (Some(current), None, None) => current,
+        (None, None, _) => 0,
+    };
+
+    if current != Some(target) {
+        if let Some(current) = current {
+            commands.entity(order[current].0).remove::<Focused>();
+        }
+        commands.entity(order[target].0).insert(Focused);
+    } else if input.just_pressed(&ACTIVATE_KEYS, &[GamepadButtonType::South]) {
+        activations.send(ActivateFocused);
+    }
+}
+
+// Handles keybindings for the menu. Escape, or the east button of a gamepad, goes back one screen,
+// and quits from the main menu. Enter is left to the focused button of the screen
 pub fn keybinding_system(
-    input: Res<Input<KeyCode>>,
-    game_state: Res<State<ActiveGameState>>,
+    input: MenuInput,
+    menu_state: Res<State<MenuState>>,
     mut app_exit_events: EventWriter<AppExit>,
     mut next_menu_state: ResMut<NextState<MenuState>>,
-    mut next_arcade_state: ResMut<NextState<ArcadeState>>,
 ) {
-    if input.just_pressed(KeyCode::Return) {
-        next_arcade_state.set(game_state.as_ref().0.as_arcade_state());
-        next_menu_state.set(MenuState::Disabled);
-    } else if input.just_pressed(KeyCode::Escape) {
-        app_exit_events.send(AppExit);
+    if input.just_pressed(&[KeyCode::Escape], &[GamepadButtonType::East]) {
+        match menu_state.0 {
+            MenuState::Main => app_exit_events.send(AppExit),
+            MenuState::GameSelection => next_menu_state.set(MenuState::Main),
+            MenuState::MinesweeperOptions | MenuState::MinesweeperStats | MenuState::TronOptions => {
+                next_menu_state.set(MenuState::GameSelection);
+            },
+            MenuState::Disabled => (),
+        }
     }
 }
 
-// Sets state based on the MenuButtonActions
+// Sets state based on the MenuButtonActions, of a clicked or an activated button
 pub fn menu_action(
     interaction_query: Query<MouseMainMenuInteraction, With<Button>>,
+    mut activations: EventReader<ActivateFocused>,
     game_state: Res<State<ActiveGameState>>,
     mut app_exit_events: EventWriter<AppExit>,
     mut next_menu_state: ResMut<NextState<MenuState>>,
     mut next_arcade_state: ResMut<NextState<ArcadeState>>,
 ) {
-    for (interaction, menu_button_action) in &interaction_query {
-        if *interaction == Interaction::Clicked {
+    let activated = activations.iter().count() > 0;
+    for (interaction, menu_button_action, focused) in &interaction_query {
+        if *interaction == Interaction::Clicked || activated && focused.is_some() {
             match menu_button_action {
                 MainMenuButtonAction::Quit => app_exit_events.send(AppExit),
                 MainMenuButtonAction::Play => {
@@ -73,14 +207,16 @@ pub fn menu_action(
     }
 }
 
-// Sets state based on the GameListButtonActions
+// Sets state based on the GameListButtonActions, of a clicked or an activated button
 pub fn game_list_action(
     interaction_query: Query<MouseGameMenuInteraction, With<Button>>,
+    mut activations: EventReader<ActivateFocused>,
     mut next_menu_state: ResMut<NextState<MenuState>>,
     mut next_game_state: ResMut<NextState<ActiveGameState>>,
 ) {
-    for (interaction, game_list_button_action) in &interaction_query {
-        if *interaction == Interaction::Clicked {
+    let activated = activations.iter().count() > 0;
+    for (interaction, game_list_button_action, focused) in &interaction_query {
+        if *interaction == Interaction::Clicked || activated && focused.is_some() {
             match game_list_button_action {
                 GameMenuButtonAction::PlaySnake => {
                     next_game_state.set(ActiveGameState::Snake);
@@ -88,12 +224,233 @@ pub fn game_list_action(
                 GameMenuButtonAction::PlayMinesweeper => {
                     next_game_state.set(ActiveGameState::Minesweeper);
                 },
+                GameMenuButtonAction::PlayMinesweeperVersus => {
+                    next_game_state.set(ActiveGameState::MinesweeperVersus);
+                },
+                GameMenuButtonAction::PlayTron => {
+                    next_game_state.set(ActiveGameState::Tron);
+                },
+                GameMenuButtonAction::MinesweeperOptions => {
+                    next_menu_state.set(MenuState::MinesweeperOptions);
+                },
+                GameMenuButtonAction::MinesweeperStats => {
+                    next_menu_state.set(MenuState::MinesweeperStats);
+                },
+                GameMenuButtonAction::TronOptions => {
+                    next_menu_state.set(MenuState::TronOptions);
+                },
                 GameMenuButtonAction::BackToMainMenu => next_menu_state.set(MenuState::Main),
             }
         }
     }
 }
 
+// Sets state based on the OptionsMenuButtonActions, of a clicked or an activated button
+pub fn options_action(
+    interaction_query: Query<MouseOptionsMenuInteraction, With<Button>>,
+    mut activations: EventReader<ActivateFocused>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+) {
+    let activated = activations.iter().count() > 0;
+    for (interaction, options_button_action, focused) in &interaction_query {
+        if *interaction == Interaction::Clicked || activated && focused.is_some() {
+            match options_button_action {
+                OptionsMenuButtonAction::BackToGameList => {
+                    next_menu_state.set(MenuState::GameSelection);
+                },
+            }
+        }
+    }
+}
+
+// Sets state based on the StatsMenuButtonActions, of a clicked or an activated button
+pub fn stats_action(
+    interaction_query: Query<MouseStatsMenuInteraction, With<Button>>,
+    mut activations: EventReader<ActivateFocused>,
+    mut replay: ResMut<MinesweeperReplay>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+    mut next_arcade_state: ResMut<NextState<ArcadeState>>,
+) {
+    let activated = activations.iter().count() > 0;
+    for (interaction, stats_button_action, focused) in &interaction_query {
+        if *interaction == Interaction::Clicked || activated && focused.is_some() {
+            match stats_button_action {
+                StatsMenuButtonAction::WatchReplay(selected) => {
+                    *replay = selected.clone();
+                    next_arcade_state.set(ArcadeState::ReplayingMinesweeper);
+                    next_menu_state.set(MenuState::Disabled);
+                },
+                StatsMenuButtonAction::BackToGameList => {
+                    next_menu_state.set(MenuState::GameSelection);
+                },
+            }
+        }
+    }
+}
+
+// Sets state based on the TronMenuButtonActions, of a clicked or an activated button
+pub fn tron_options_action(
+    interaction_query: Query<MouseTronMenuInteraction, With<Button>>,
+    mut activations: EventReader<ActivateFocused>,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+) {
+    let activated = activations.iter().count() > 0;
+    for (interaction, tron_button_action, focused) in &interaction_query {
+        if *interaction == Interaction::Clicked || activated && focused.is_some() {
+            match tron_button_action {
+                TronMenuButtonAction::BackToGameList => {
+                    next_menu_state.set(MenuState::GameSelection);
+                },
+            }
+        }
+    }
+}
+
+// Selects the difficulty of the clicked or activated difficulty button
+pub fn difficulty_button_system(
+    interaction_query: Query<SettingButton<DifficultyButton>>,
+    mut activations: EventReader<ActivateFocused>,
+    mut settings: ResMut<MinesweeperSettings>,
+) {
+    let activated = activations.iter().count() > 0;
+    for (interaction, difficulty, focused) in &interaction_query {
+        if setting_pressed(&interaction, focused, activated) {
+            settings.difficulty = difficulty.0;
+        }
+    }
+}
+
+// Marks the button of the current difficulty as the selected option
+pub fn difficulty_selection_system(
+    mut commands: Commands,
+    settings: Res<MinesweeperSettings>,
+    buttons: Query<(Entity, &DifficultyButton, Option<&SelectedOption>)>,
+) {
+    for (entity, difficulty, selected) in &buttons {
+        match (difficulty.0 == settings.difficulty, selected) {
+            (true, None) => { commands.entity(entity).insert(SelectedOption); },
+            (false, Some(_)) => { commands.entity(entity).remove::<SelectedOption>(); },
+            _ => (),
+        }
+    }
+}
+
+// Selects the topology of the clicked or activated topology button
+pub fn topology_button_system(
+    interaction_query: Query<SettingButton<TopologyButton>>,
+    mut activations: EventReader<ActivateFocused>,
+    mut settings: ResMut<MinesweeperSettings>,
+) {
+    let activated = activations.iter().count() > 0;
+    for (interaction, topology, focused) in &interaction_query {
+        if setting_pressed(&interaction, focused, activated) {
+            settings.topology = topology.0;
+        }
+    }
+}
+
+// Marks the button of the current topology as the selected option
+pub fn topology_selection_system(
+    mut commands: Commands,
+    settings: Res<MinesweeperSettings>,
+    buttons: Query<(Entity, &TopologyButton, Option<&SelectedOption>)>,
+) {
+    for (entity, topology, selected) in &buttons {
+        match (topology.0 == settings.topology, selected) {
+            (true, None) => { commands.entity(entity).insert(SelectedOption); },
+            (false, Some(_)) => { commands.entity(entity).remove::<SelectedOption>(); },
+            _ => (),
+        }
+    }
+}
+
+// Changes the custom board, which is only accepted if the resulting board is valid
+pub fn custom_board_button_system(
+    interaction_query: Query<SettingButton<CustomBoardButton>>,
+    mut activations: EventReader<ActivateFocused>,
+    keys: Res<Input<KeyCode>>,
+    mut error_text: Query<&mut Text, With<OptionsErrorText>>,
+    mut settings: ResMut<MinesweeperSettings>,
+) {
+    // Boards can be up to a few hundred tiles wide, so shift makes the buttons take bigger steps
+    let step = if keys.any_pressed([KeyCode::LShift, KeyCode::RShift]) { 10 } else { 1 };
+
+    let activated = activations.iter().count() > 0;
+    for (interaction, CustomBoardButton(field, delta), focused) in &interaction_query {
+        if !setting_pressed(&interaction, focused, activated) {
+            continue;
+        }
+        let delta = delta * step;
+
+        let custom = settings.custom;
+        let (width, height, num_mines) = match field {
+            CustomBoardField::Width => (custom.get_width() + delta, custom.get_height(), custom.get_num_mines()),
+            CustomBoardField::Height => (custom.get_width(), custom.get_height() + delta, custom.get_num_mines()),
+            CustomBoardField::Mines => (custom.get_width(), custom.get_height(), custom.get_num_mines() + delta),
+        };
+
+        let message = match CustomBoard::new(width, height, num_mines) {
+            Ok(custom) => {
+                settings.custom = custom;
+                settings.difficulty = Difficulty::Custom;
+                String::new()
+            },
+            Err(e) => e.to_string(),
+        };
+        for mut text in &mut error_text {
+            text.sections[0].value = message.clone();
+        }
+    }
+}
+
+// Displays the values of the custom board
+pub fn custom_board_text_system(
+    settings: Res<MinesweeperSettings>,
+    mut text_query: Query<(&mut Text, &CustomBoardText)>,
+) {
+    for (mut text, CustomBoardText(field)) in &mut text_query {
+        text.sections[0].value = match field {
+            CustomBoardField::Width => settings.custom.get_width(),
+            CustomBoardField::Height => settings.custom.get_height(),
+            CustomBoardField::Mines => settings.custom.get_num_mines(),
+        }.to_string();
+    }
+}
+
+// Changes the number of lives, which is kept between one and the most lives there can be. An
+// endless run always has one life, so the lives can't be changed for it
+pub fn lives_button_system(
+    interaction_query: Query<SettingButton<LivesButton>>,
+    mut activations: EventReader<ActivateFocused>,
+    mut error_text: Query<&mut Text, With<OptionsErrorText>>,
+    mut settings: ResMut<MinesweeperSettings>,
+) {
+    let activated = activations.iter().count() > 0;
+    for (interaction, LivesButton(delta), focused) in &interaction_query {
+        if !setting_pressed(&interaction, focused, activated) {
+            continue;
+        }
+        let message = if settings.endless {
+            "An endless run always ends at the first mine".to_string()
+        } else {
+            settings.lives = (settings.lives as i32 + delta).clamp(1, MAX_LIVES as i32) as usize;
+            String::new()
+        };
+        for mut text in &mut error_text {
+            text.sections[0].value = message.clone();
+        }
+    }
+}
+
+pub fn lives_text_system(
+    settings: Res<MinesweeperSettings>,
+    mut text_query: Query<&mut Text, With<LivesText>>,
+) {
+    for mut text in &mut text_query {
+        text.sections[0].value = settings.lives().to_string();
+    }
+}
+
 // Updates play button text based on active game state
 // NOTE: This is a workaround for the lack of dynamic text in bevy. Really convoluted and not
 // pretty
@@ -109,9 +466,97 @@ pub fn text_update_system(
                     text.sections[0].value = match game_state.as_ref().0 {
                         ActiveGameState::Snake => "Play Snake",
                         ActiveGameState::Minesweeper => "Play Minesweeper",
+                        ActiveGameState::MinesweeperVersus => "Play Minesweeper Versus",
+                        ActiveGameState::Tron => "Play Tron",
                     }.to_string();
                 }
             }
         }
     }
 }
+
+// Toggles the on/off setting of the clicked or activated option button
+pub fn option_toggle_button_system(
+    interaction_query: Query<SettingButton<OptionToggleButton>>,
+    mut activations: EventReader<ActivateFocused>,
+    mut settings: ResMut<MinesweeperSettings>,
+) {
+    let activated = activations.iter().count() > 0;
+    for (interaction, OptionToggleButton(option), focused) in &interaction_query {
+        if setting_pressed(&interaction, focused, activated) {
+            option.toggle(&mut settings);
+        }
+    }
+}
+
+// Displays whether each toggled setting is enabled on its button
+pub fn option_toggle_text_system(
+    settings: Res<MinesweeperSettings>,
+    button_query: Query<(&Children, &OptionToggleButton)>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (children, OptionToggleButton(option)) in &button_query {
+        for child in children {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = format!(
+                    "{}: {}",
+                    option.label(),
+                    if option.is_on(&settings) { "On" } else { "Off" });
+            }
+        }
+    }
+}
+
+// Moves the clicked or activated starting position on to the next controller, or leaves it empty
+pub fn cycle_slot_button_system(
+    interaction_query: Query<SettingButton<CycleSlotButton>>,
+    mut activations: EventReader<ActivateFocused>,
+    mut settings: ResMut<TronSettings>,
+) {
+    let activated = activations.iter().count() > 0;
+    for (interaction, CycleSlotButton(slot), focused) in &interaction_query {
+        if setting_pressed(&interaction, focused, activated) {
+            settings.next_controller(*slot);
+        }
+    }
+}
+
+// Displays who steers the cycle of each starting position on its button
+pub fn cycle_slot_text_system(
+    settings: Res<TronSettings>,
+    button_query: Query<(&Children, &CycleSlotButton)>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (children, CycleSlotButton(slot)) in &button_query {
+        for child in children {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = format!(
+                    "Cycle {}: {}",
+                    slot + 1,
+                    settings.slot(*slot).map_or("Off", |controller| controller.name()));
+            }
+        }
+    }
+}
+
+pub fn tron_rounds_button_system(
+    interaction_query: Query<SettingButton<TronRoundsButton>>,
+    mut activations: EventReader<ActivateFocused>,
+    mut settings: ResMut<TronSettings>,
+) {
+    let activated = activations.iter().count() > 0;
+    for (interaction, TronRoundsButton(delta), focused) in &interaction_query {
+        if setting_pressed(&interaction, focused, activated) {
+            settings.change_best_of(*delta);
+        }
+    }
+}
+
+pub fn tron_rounds_text_system(
+    settings: Res<TronSettings>,
+    mut text_query: Query<&mut Text, With<TronRoundsText>>,
+) {
+    for mut text in &mut text_query {
+        text.sections[0].value = settings.best_of().to_string();
+    }
+}
@@ -1,5 +1,7 @@
 use bevy::prelude::{States, Component, Color};
 
+use arcade_util::{Difficulty, MinesweeperSettings, MinesweeperReplay, Topology};
+
 pub const TEXT_COLOR: Color = Color::WHITE;
 pub const BACKGROUND_COLOR: Color = Color::CRIMSON;
 pub const NORMAL_BUTTON_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
@@ -15,15 +17,54 @@ pub struct OnMainMenuScreen;
 #[derive(Component)]
 pub struct OnGamesMenuScreen;
 
+// Tag component used to tag entities added on the minesweeper options screen
+#[derive(Component)]
+pub struct OnMinesweeperOptionsScreen;
+
+// Tag component used to tag entities added on the minesweeper stats screen
+#[derive(Component)]
+pub struct OnMinesweeperStatsScreen;
+
+// Tag component used to tag entities added on the tron options screen
+#[derive(Component)]
+pub struct OnTronOptionsScreen;
+
 // Tag component used to mark which setting is currently selected
 #[derive(Component)]
 pub struct SelectedOption;
 
+// A button that can be focused with the keyboard or a gamepad, with its place in the focus order
+#[derive(Component)]
+pub struct Focusable(pub usize);
+
+// Hands out the places in the focus order to the buttons of a screen, in the order they are spawned
+#[derive(Default)]
+pub struct FocusOrder(usize);
+
+impl FocusOrder {
+    pub fn next(&mut self) -> Focusable {
+        self.0 += 1;
+        Focusable(self.0 - 1)
+    }
+}
+
+// Tag component for the button which has the focus, there is one on every screen with focusable
+// buttons
+#[derive(Component)]
+pub struct Focused;
+
+// Sent when the focused button is activated with the keyboard or a gamepad, which then acts as if
+// it was clicked
+pub struct ActivateFocused;
+
 // State used for the current menu screen
 #[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
 pub enum MenuState {
     Main,
     GameSelection,
+    MinesweeperOptions,
+    MinesweeperStats,
+    TronOptions,
     #[default]
     Disabled,
 }
@@ -41,5 +82,119 @@ pub enum MainMenuButtonAction {
 pub enum GameMenuButtonAction {
     PlaySnake,
     PlayMinesweeper,
+    PlayMinesweeperVersus,
+    PlayTron,
+    MinesweeperOptions,
+    MinesweeperStats,
+    TronOptions,
     BackToMainMenu,
 }
+
+// All actions that can be triggered from a button click on the minesweeper options menu
+#[derive(Component)]
+pub enum OptionsMenuButtonAction {
+    BackToGameList,
+}
+
+// All actions that can be triggered from a button click on the minesweeper stats menu
+#[derive(Component)]
+pub enum StatsMenuButtonAction {
+    WatchReplay(MinesweeperReplay),
+    BackToGameList,
+}
+
+// All actions that can be triggered from a button click on the tron options menu
+#[derive(Component)]
+pub enum TronMenuButtonAction {
+    BackToGameList,
+}
+
+// Button selecting one of the minesweeper difficulties
+#[derive(Component)]
+pub struct DifficultyButton(pub Difficulty);
+
+// Button selecting one of the minesweeper topologies
+#[derive(Component)]
+pub struct TopologyButton(pub Topology);
+
+// The values of a custom minesweeper board that can be changed from the options menu
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CustomBoardField {
+    Width,
+    Height,
+    Mines,
+}
+
+// Button changing a value of the custom board by the given amount
+#[derive(Component)]
+pub struct CustomBoardButton(pub CustomBoardField, pub i32);
+
+// Text displaying a value of the custom board
+#[derive(Component)]
+pub struct CustomBoardText(pub CustomBoardField);
+
+// Button changing the number of minesweeper lives by the given amount
+#[derive(Component)]
+pub struct LivesButton(pub i32);
+
+// Text displaying the number of minesweeper lives
+#[derive(Component)]
+pub struct LivesText;
+
+// Button going through the controllers of the tron cycle on the given starting position
+#[derive(Component)]
+pub struct CycleSlotButton(pub usize);
+
+// Button changing the number of tron rounds by the given amount
+#[derive(Component)]
+pub struct TronRoundsButton(pub i32);
+
+// Text displaying the number of tron rounds
+#[derive(Component)]
+pub struct TronRoundsText;
+
+// The on/off minesweeper settings that can be toggled from the options screen
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OptionToggle {
+    QuestionMarks,
+    NoGuess,
+    Practice,
+    Endless,
+}
+
+impl OptionToggle {
+    pub fn label(&self) -> &'static str {
+        match self {
+            OptionToggle::QuestionMarks => "Question marks",
+            OptionToggle::NoGuess => "No guessing",
+            OptionToggle::Practice => "Practice mode",
+            OptionToggle::Endless => "Endless board",
+        }
+    }
+
+    pub fn is_on(&self, settings: &MinesweeperSettings) -> bool {
+        match self {
+            OptionToggle::QuestionMarks => settings.question_marks,
+            OptionToggle::NoGuess => settings.no_guess,
+            OptionToggle::Practice => settings.practice,
+            OptionToggle::Endless => settings.endless,
+        }
+    }
+
+    pub fn toggle(&self, settings: &mut MinesweeperSettings) {
+        match self {
+            OptionToggle::QuestionMarks => settings.question_marks = !settings.question_marks,
+            OptionToggle::NoGuess => settings.no_guess = !settings.no_guess,
+            OptionToggle::Practice => settings.practice = !settings.practice,
+            OptionToggle::Endless => settings.endless = !settings.endless,
+        }
+    }
+}
+
+// Button toggling one of the on/off minesweeper settings
+#[derive(Component)]
+pub struct OptionToggleButton(pub OptionToggle);
+
+// Text displaying why the custom board could not be changed
+#[derive(Component)]
+pub struct OptionsErrorText;
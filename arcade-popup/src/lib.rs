@@ -3,4 +3,4 @@ mod systems;
 mod plugin;
 
 pub use plugin::PopupPlugin;
-pub use util::spawn_popup;
+pub use util::{spawn_popup, spawn_popup_then};
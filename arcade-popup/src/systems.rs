@@ -131,12 +131,13 @@ pub fn spawn_popup_window(
 // This system handles the buttons background changes
 pub fn button_system(
     mut interaction_query: Query<(&Interaction, &mut BackgroundColor), With<Button>>,
+    message: Res<PopupMessage>,
     mut next_arcade_state: ResMut<NextState<ArcadeState>>,
 ) {
     for (interaction, mut color) in &mut interaction_query {
         *color = match *interaction {
             Interaction::Clicked => {
-                next_arcade_state.set(ArcadeState::Menu);
+                next_arcade_state.set(message.get_next_state().clone());
                 PRESSED_BUTTON_COLOR.into()
             },
             Interaction::Hovered => HOVERED_BUTTON_COLOR.into(),
@@ -3,6 +3,8 @@ use bevy::{
     ecs::system::Command
 };
 
+use arcade_util::ArcadeState;
+
 pub const TEXT_COLOR: Color = Color::WHITE;
 pub const POPUP_COLOR: Color = Color::CRIMSON;
 pub const NORMAL_BUTTON_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
@@ -14,11 +16,13 @@ pub const PRESSED_BUTTON_COLOR: Color = Color::rgb(0.35, 0.75, 0.35);
 pub struct PopupMessage {
     header: String,
     body: String,
+    next_state: ArcadeState,
 }
 
 impl PopupMessage {
     pub fn get_header(&self) -> &String { &self.header }
     pub fn get_body(&self) -> &String { &self.body }
+    pub fn get_next_state(&self) -> &ArcadeState { &self.next_state }
 }
 
 impl Default for PopupMessage {
@@ -26,6 +30,7 @@ impl Default for PopupMessage {
         Self {
             header: String::from("Header"),
             body: String::from("Body"),
+            next_state: ArcadeState::Menu,
         }
     }
 }
@@ -34,6 +39,7 @@ impl Default for PopupMessage {
 pub struct ChangePopupMessage {
     header: String,
     body: String,
+    next_state: ArcadeState,
 }
 
 impl Command for ChangePopupMessage {
@@ -41,15 +47,26 @@ impl Command for ChangePopupMessage {
         let mut popup = world.get_resource_or_insert_with(PopupMessage::default);
         popup.header = self.header;
         popup.body = self.body;
+        popup.next_state = self.next_state;
     }
 }
 
 pub fn spawn_popup(
+    commands: Commands,
+    header: String,
+    body: String,
+) {
+    spawn_popup_then(commands, header, body, ArcadeState::Menu);
+}
+
+// Same as spawn_popup, but pressing continue moves to the given state instead of the menu
+pub fn spawn_popup_then(
     mut commands: Commands,
     header: String,
     body: String,
+    next_state: ArcadeState,
 ) {
-    let message = ChangePopupMessage { header, body, };
+    let message = ChangePopupMessage { header, body, next_state, };
     commands.add(message.clone());
 }
 
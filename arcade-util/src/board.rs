@@ -1,57 +1,189 @@
-use bevy::prelude::{Component, Color, default, Transform, Vec2};
+use bevy::prelude::{Camera, Component, Color, default, GlobalTransform, Transform, Vec2};
+use bevy::window::Window;
 use bevy::sprite::{SpriteBundle, Sprite};
 
+use crate::{Coord2D, Topology};
+
 #[derive(Component)]
 pub struct DiscreteBoard {
     tile_size: f32,
     tile_spacing: f32,
-    size: i32,
-    physical_size: f32,
+    width: i32,
+    height: i32,
+    physical_size: Vec2,
+    // Unbounded boards go on in every direction from the single cell they are made of
+    bounded: bool,
+    // Square unless set otherwise, the rows of a hexagonal board are staggered
+    topology: Topology,
 }
 
 impl DiscreteBoard {
     pub fn new(size: i32, tile_size: f32, tile_spacing: f32) -> Self {
-        let physical_size = Self::pos_to_physical(size, tile_size, tile_spacing);
-        Self { tile_size, tile_spacing, size, physical_size }
+        Self::rectangular(size, size, tile_size, tile_spacing)
+    }
+
+    pub fn rectangular(width: i32, height: i32, tile_size: f32, tile_spacing: f32) -> Self {
+        let physical_size = Vec2::new(
+            Self::pos_to_physical(width, tile_size, tile_spacing),
+            Self::pos_to_physical(height, tile_size, tile_spacing),
+        );
+        Self {
+            tile_size,
+            tile_spacing,
+            width,
+            height,
+            physical_size,
+            bounded: true,
+            topology: Topology::Square,
+        }
+    }
+
+    // A board without edges, with cell (0, 0) at the center. Its size is that of the one cell
+    pub fn unbounded(tile_size: f32, tile_spacing: f32) -> Self {
+        Self { bounded: false, ..Self::rectangular(1, 1, tile_size, tile_spacing) }
+    }
+
+    // The same board with its cells connected by the given topology. A staggered board is half a
+    // cell wider when it has rows to stagger, for the shifted rows to fit
+    pub fn with_topology(self, topology: Topology) -> Self {
+        let mut physical_size = Self::rectangular(
+            self.width, self.height, self.tile_size, self.tile_spacing).physical_size;
+        if topology.is_staggered() && self.height > 1 {
+            physical_size.x += self.row_shift();
+        }
+        Self { topology, physical_size, ..self }
     }
 
     fn pos_to_physical(pos: i32, tile_size: f32, tile_spacing: f32) -> f32 {
         pos as f32 * tile_size + (pos + 1) as f32 * tile_spacing
     }
 
-    pub fn cell_pos_to_physical_pos(&self, pos: i32) -> f32 {
-        let offset = -self.physical_size / 2.0 + 0.5 * self.tile_size;
-        offset + Self::pos_to_physical(pos, self.tile_size, self.tile_spacing)
+    // How far the odd rows of a staggered board are shifted to the right
+    fn row_shift(&self) -> f32 {
+        (self.tile_size + self.tile_spacing) / 2.0
+    }
+
+    fn row_offset(&self, y: i32) -> f32 {
+        if self.topology.is_staggered() && y.rem_euclid(2) == 1 {
+            self.row_shift()
+        } else {
+            0.0
+        }
+    }
+
+    // The board is centered around the origin, so the offset is half of the physical size
+    fn offset(&self) -> Vec2 {
+        -self.physical_size / 2.0 + 0.5 * self.tile_size
+    }
+
+    pub fn cell_pos_to_physical_pos(&self, coord: Coord2D<i32>) -> Vec2 {
+        self.offset() + Vec2::new(
+            Self::pos_to_physical(coord.0, self.tile_size, self.tile_spacing) + self.row_offset(coord.1),
+            Self::pos_to_physical(coord.1, self.tile_size, self.tile_spacing),
+        )
+    }
+
+    // The row is found first, since the cells of a staggered board are shifted along with it
+    pub fn physical_pos_to_cell_pos(&self, pos: Vec2) -> Coord2D<i32> {
+        let cell = (pos - self.offset() - self.tile_spacing) / (self.tile_size + self.tile_spacing);
+        let y = cell.y.round() as i32;
+        let x = cell.x - self.row_offset(y) / (self.tile_size + self.tile_spacing);
+        Coord2D(x.round() as i32, y)
     }
 
-    pub fn physical_pos_to_cell_pos(&self, pos: f32) -> i32 {
-        let offset = -self.physical_size / 2.0 + 0.5 * self.tile_size;
-        ((pos - offset - self.tile_spacing) / (self.tile_size + self.tile_spacing))
-            .round() as i32
+    // Whether a position relative to the center of the board lies on the board
+    pub fn contains_physical_pos(&self, pos: Vec2) -> bool {
+        !self.bounded || pos.abs().cmple(self.physical_size / 2.0).all()
     }
 
+    // The cell under the cursor in the given window, if any. The cursor is taken through the
+    // camera into the world and from there into the board's own space, so that picking keeps
+    // working when the camera or the board is moved or zoomed, and for any window scale factor
+    pub fn cursor_to_coord(
+        &self,
+        window: &Window,
+        camera: &Camera,
+        camera_transform: &GlobalTransform,
+        board_transform: &GlobalTransform,
+    ) -> Option<Coord2D<i32>> {
+        let cursor = window.cursor_position()?;
+
+        // The cursor is measured from the bottom left of the window, while the viewport of the
+        // camera is placed from the top left
+        let viewport_position = match camera.logical_viewport_rect() {
+            Some((min, max)) => Vec2::new(cursor.x - min.x, cursor.y - (window.height() - max.y)),
+            None => cursor,
+        };
+        let world_position = camera.viewport_to_world_2d(camera_transform, viewport_position)?;
+        self.world_pos_to_coord(board_transform, world_position)
+    }
+
+    // The cell at the given position in the world, if any, for a board placed by the given
+    // transform
+    pub fn world_pos_to_coord(
+        &self,
+        board_transform: &GlobalTransform,
+        world_position: Vec2,
+    ) -> Option<Coord2D<i32>> {
+        let local_position = board_transform.compute_matrix()
+            .inverse()
+            .transform_point3(world_position.extend(0.0))
+            .truncate();
+
+        if !self.contains_physical_pos(local_position) {
+            return None;
+        }
+        let coord = self.physical_pos_to_cell_pos(local_position);
+        self.is_coord_valid(coord.0, coord.1).then_some(coord)
+    }
+
+    // The side length of a square board
     pub fn get_size(&self) -> i32 {
-        self.size
+        self.width
+    }
+
+    pub fn get_width(&self) -> i32 {
+        self.width
     }
 
-    pub fn get_physical_size(&self) -> f32 {
+    pub fn get_height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn get_physical_size(&self) -> Vec2 {
         self.physical_size
     }
 
+    pub fn is_bounded(&self) -> bool {
+        self.bounded
+    }
+
+    pub fn get_topology(&self) -> Topology {
+        self.topology
+    }
+
     pub fn is_coord_valid(&self, x: i32, y: i32) -> bool {
-        x >= 0 && x < self.size && y >= 0 && y < self.size
+        !self.bounded || x >= 0 && x < self.width && y >= 0 && y < self.height
+    }
+
+    // The cell a coordinate ends up at on the board, if any, which on a torus wraps around the
+    // edges
+    pub fn place_coord(&self, coord: Coord2D<i32>) -> Option<Coord2D<i32>> {
+        if !self.bounded {
+            return Some(coord);
+        }
+        self.topology.place(coord, (self.width, self.height))
     }
 
     pub fn tile_sprite_at_coord(&self, x: i32, y: i32, z: i32, color: Color) -> SpriteBundle {
-        let x = self.cell_pos_to_physical_pos(x);
-        let y = self.cell_pos_to_physical_pos(y);
+        let pos = self.cell_pos_to_physical_pos(Coord2D(x, y));
         SpriteBundle {
             sprite: Sprite {
                 color,
                 custom_size: Some(Vec2::new(self.tile_size, self.tile_size)),
                 ..default()
             },
-            transform: Transform::from_xyz(x, y, z as f32),
+            transform: Transform::from_xyz(pos.x, pos.y, z as f32),
             ..default()
         }
     }
@@ -0,0 +1,165 @@
+use bevy::input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel};
+use bevy::math::Rect;
+use bevy::prelude::{
+    Camera,
+    Component,
+    EventReader,
+    GlobalTransform,
+    Input,
+    KeyCode,
+    MouseButton,
+    OrthographicProjection,
+    Query,
+    Res,
+    Transform,
+    Vec2,
+    With,
+};
+use bevy::time::Time;
+use bevy::window::Window;
+
+// Holding one of these turns the arrow keys and dragging with the left mouse button into panning,
+// so that games can keep using them for play
+pub const PAN_MODIFIERS: [KeyCode; 2] = [KeyCode::LShift, KeyCode::RShift];
+
+// The scales of the camera projection that can be zoomed to, a larger scale shows more. The camera
+// zooms out further than the maximum when that is needed to fit an area, see fit_camera
+pub const MIN_ZOOM: f32 = 0.25;
+pub const MAX_ZOOM: f32 = 8.0;
+
+// Zoom factor of one line of the scroll wheel
+const ZOOM_STEP: f32 = 1.1;
+// Scrolling in pixels, as touchpads do, counts this many pixels as a line
+const PIXELS_PER_LINE: f32 = 20.0;
+// Panning speed with the arrow keys, in pixels on screen per second
+const PAN_SPEED: f32 = 600.0;
+
+// Marks the 2D camera spawned by the DefaultArcadePlugin, with the largest scale it can be zoomed
+// out to
+#[derive(Component)]
+pub struct ArcadeCamera {
+    max_zoom: f32,
+}
+
+impl Default for ArcadeCamera {
+    fn default() -> Self {
+        ArcadeCamera { max_zoom: MAX_ZOOM }
+    }
+}
+
+type ArcadeCameraQuery<'a> = (&'a mut Transform, &'a mut OrthographicProjection);
+pub type FitCameraQuery<'a> = (
+    &'a mut ArcadeCamera,
+    &'a mut Transform,
+    &'a mut OrthographicProjection,
+);
+type ZoomCameraQuery<'a> = (&'a Camera, &'a GlobalTransform, &'a ArcadeCamera, ArcadeCameraQuery<'a>);
+
+// Pans the camera with the arrow keys, or by dragging with the left mouse button, while a pan
+// modifier is held. The camera moves as far on screen at every zoom
+pub fn pan_camera(
+    time: Res<Time>,
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut motion: EventReader<MouseMotion>,
+    mut camera: Query<ArcadeCameraQuery, With<ArcadeCamera>>,
+) {
+    let dragged: Vec2 = motion.iter().map(|motion| motion.delta).sum();
+    if !keys.any_pressed(PAN_MODIFIERS) {
+        return;
+    }
+
+    let mut pan = Vec2::ZERO;
+    for (key, direction) in [
+        (KeyCode::Left, Vec2::NEG_X),
+        (KeyCode::Right, Vec2::X),
+        (KeyCode::Down, Vec2::NEG_Y),
+        (KeyCode::Up, Vec2::Y),
+    ] {
+        if keys.pressed(key) {
+            pan += direction * PAN_SPEED * time.delta_seconds();
+        }
+    }
+    // The world follows the mouse, and the mouse moves down the screen for a positive y
+    if mouse_buttons.pressed(MouseButton::Left) {
+        pan += Vec2::new(-dragged.x, dragged.y);
+    }
+
+    for (mut transform, projection) in camera.iter_mut() {
+        transform.translation += (pan * projection.scale).extend(0.0);
+    }
+}
+
+// Run condition for the mouse and key handling of a game, which is left to the camera while a pan
+// modifier is held
+pub fn not_panning(keys: Res<Input<KeyCode>>) -> bool {
+    !keys.any_pressed(PAN_MODIFIERS)
+}
+
+// Zooms the camera with the scroll wheel, keeping the point under the mouse in place
+pub fn zoom_camera(
+    mut wheel: EventReader<MouseWheel>,
+    windows: Query<&Window>,
+    mut camera: Query<ZoomCameraQuery>,
+) {
+    let lines: f32 = wheel.iter()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / PIXELS_PER_LINE,
+        })
+        .sum();
+    if lines == 0.0 {
+        return;
+    }
+    let cursor = windows.get_single().ok().and_then(|window| window.cursor_position());
+
+    for (camera, global_transform, limits, (mut transform, mut projection)) in camera.iter_mut() {
+        let scale = (projection.scale * ZOOM_STEP.powf(-lines)).clamp(MIN_ZOOM, limits.max_zoom);
+
+        // The point under the mouse is as far from the center on screen before as after
+        let anchor = cursor.and_then(|cursor| camera.viewport_to_world_2d(global_transform, cursor));
+        if let Some(anchor) = anchor {
+            let center = transform.translation.truncate();
+            let moved = anchor + (center - anchor) * scale / projection.scale;
+            transform.translation = moved.extend(transform.translation.z);
+        }
+        projection.scale = scale;
+    }
+}
+
+// Centers the camera on the origin and zooms it such that an area of the given size fits the
+// window, keeping a margin in pixels on every side. It is never zoomed in beyond the normal scale.
+// Until the camera is reset, it can be zoomed out as far as the area needs, if that is beyond the
+// maximum zoom
+pub fn fit_camera(
+    window: &Window,
+    size: Vec2,
+    margin: f32,
+    (camera, transform, projection): FitCameraQuery,
+) {
+    let available = (Vec2::new(window.width(), window.height()) - 2.0 * margin).max(Vec2::ONE);
+    let scale = (size / available).max_element().max(1.0);
+    camera.max_zoom = scale.max(MAX_ZOOM);
+    transform.translation.x = 0.0;
+    transform.translation.y = 0.0;
+    projection.scale = scale;
+}
+
+// Puts the camera back at the origin at the normal scale
+pub fn reset_camera(mut camera: Query<FitCameraQuery>) {
+    for (mut camera, mut transform, mut projection) in camera.iter_mut() {
+        camera.max_zoom = MAX_ZOOM;
+        transform.translation.x = 0.0;
+        transform.translation.y = 0.0;
+        projection.scale = 1.0;
+    }
+}
+
+// The part of the world the camera shows
+pub fn visible_world_rect(camera: &Camera, camera_transform: &GlobalTransform) -> Option<Rect> {
+    let (min, max) = camera.logical_viewport_rect()?;
+    let size = max - min;
+    let corner = camera.viewport_to_world_2d(camera_transform, Vec2::ZERO)?;
+    let opposite = camera.viewport_to_world_2d(camera_transform, size)?;
+    Some(Rect::from_corners(corner, opposite))
+}
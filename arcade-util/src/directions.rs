@@ -24,6 +24,15 @@ impl Dir2D {
         }
     }
 
+    pub fn opposite(&self) -> Dir2D {
+        match self {
+            Dir2D::Up => Dir2D::Down,
+            Dir2D::Down => Dir2D::Up,
+            Dir2D::Left => Dir2D::Right,
+            Dir2D::Right => Dir2D::Left,
+        }
+    }
+
     pub fn as_coord(&self) -> Coord2D<i32> {
         match self {
             Dir2D::Up => Coord2D(0, 1),
@@ -5,9 +5,15 @@ mod statuses;
 mod traits;
 mod plugins;
 mod systems;
+mod saves;
+mod settings;
+mod stats;
+mod camera;
+mod topology;
 
 pub use coords::Coord2D;
 pub use board::DiscreteBoard;
+pub use topology::Topology;
 pub use directions::{
     Dir2D,
     Rot2D,
@@ -22,4 +28,37 @@ pub use traits::{
     CoordConfiguration,
 };
 pub use plugins::DefaultArcadePlugin;
+pub use camera::{
+    ArcadeCamera,
+    FitCameraQuery,
+    PAN_MODIFIERS,
+    MIN_ZOOM,
+    MAX_ZOOM,
+    pan_camera,
+    not_panning,
+    zoom_camera,
+    fit_camera,
+    reset_camera,
+    visible_world_rect,
+};
 pub use systems::despawn_component;
+pub use saves::save_path;
+pub use settings::{
+    MAX_LIVES,
+    MIN_CYCLES,
+    MAX_CYCLES,
+    MAX_TRON_ROUNDS,
+    MinesweeperSettings,
+    SafeStart,
+    Difficulty,
+    CustomBoard,
+    MinesweeperReplay,
+    KeyScheme,
+    CycleController,
+    TronSettings,
+};
+pub use stats::{
+    MinesweeperStats,
+    BoardStats,
+    RunScores,
+};
@@ -12,6 +12,7 @@ use bevy::{
 };
 
 use crate::ArcadeState;
+use crate::camera::ArcadeCamera;
 
 pub struct DefaultArcadePlugin;
 
@@ -30,5 +31,5 @@ impl Plugin for DefaultArcadePlugin {
 }
 
 fn setup_camera(mut commands: Commands) {
-    commands.spawn(Camera2dBundle::default());
+    commands.spawn(Camera2dBundle::default()).insert(ArcadeCamera::default());
 }
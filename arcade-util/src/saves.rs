@@ -0,0 +1,46 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::ArcadeError;
+
+// The saves are kept in this folder next to the executable, so that they go along wherever the game
+// is copied or installed, and stay in the same place however it is launched
+const SAVE_FOLDER: &str = "saves";
+
+// Returns the path of a save file (high scores, recordings, ...) for the given game, creating the
+// game's save folder if it does not exist yet
+pub fn save_path(game: &str, file: &str) -> Result<PathBuf, ArcadeError> {
+    let executable = std::env::current_exe()?;
+    let Some(executable_folder) = executable.parent() else {
+        return Err(ArcadeError::new("The folder of the executable could not be found"));
+    };
+    let folder = executable_folder.join(SAVE_FOLDER).join(game);
+    std::fs::create_dir_all(&folder)?;
+    Ok(folder.join(file))
+}
+
+// Reads the `key=value` lines of a save file, which has none if it does not exist yet
+pub(crate) fn read_key_values(game: &str, file: &str) -> Result<BTreeMap<String, String>, ArcadeError> {
+    let path = save_path(game, file)?;
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    Ok(std::fs::read_to_string(path)?
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.trim().to_string()))
+        .collect())
+}
+
+// Writes the values as `key=value` lines to a save file
+pub(crate) fn write_key_values(
+    game: &str,
+    file: &str,
+    values: &BTreeMap<String, impl std::fmt::Display>,
+) -> Result<(), ArcadeError> {
+    let contents: String = values.iter()
+        .map(|(key, value)| format!("{}={}\n", key, value))
+        .collect();
+    std::fs::write(save_path(game, file)?, contents)?;
+    Ok(())
+}
@@ -0,0 +1,309 @@
+use bevy::prelude::{Resource, KeyCode};
+
+use crate::{ArcadeError, Topology};
+
+pub const MIN_BOARD_DIMENSION: i32 = 5;
+pub const MAX_BOARD_DIMENSION: i32 = 200;
+pub const MAX_LIVES: usize = 9;
+pub const MIN_CYCLES: usize = 2;
+pub const MAX_CYCLES: usize = 4;
+pub const MAX_TRON_ROUNDS: u32 = 9;
+
+// How much of the board around the first revealed tile is guaranteed to be free of mines
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum SafeStart {
+    // Only the first revealed tile itself
+    Cell,
+    // The first revealed tile and its neighbors, so the first reveal always opens up an area
+    #[default]
+    Opening,
+}
+
+// The difficulty presets of minesweeper
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Difficulty {
+    #[default]
+    Beginner,
+    Intermediate,
+    Expert,
+    Custom,
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Beginner => "Beginner",
+            Difficulty::Intermediate => "Intermediate",
+            Difficulty::Expert => "Expert",
+            Difficulty::Custom => "Custom",
+        }
+    }
+}
+
+// A user defined board, which can only be constructed with valid dimensions
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CustomBoard {
+    width: i32,
+    height: i32,
+    num_mines: i32,
+}
+
+impl CustomBoard {
+    pub fn new(width: i32, height: i32, num_mines: i32) -> Result<Self, ArcadeError> {
+        let dimensions = MIN_BOARD_DIMENSION..=MAX_BOARD_DIMENSION;
+        if !dimensions.contains(&width) || !dimensions.contains(&height) {
+            return Err(ArcadeError::new(format!(
+                "Board dimensions must be between {} and {}",
+                MIN_BOARD_DIMENSION, MAX_BOARD_DIMENSION)));
+        }
+        // At least the first revealed tile has to be free of mines
+        if num_mines < 1 || num_mines >= width * height {
+            return Err(ArcadeError::new(format!(
+                "A {}x{} board fits between 1 and {} mines",
+                width, height, width * height - 1)));
+        }
+        Ok(Self { width, height, num_mines })
+    }
+
+    pub fn get_width(&self) -> i32 { self.width }
+    pub fn get_height(&self) -> i32 { self.height }
+    pub fn get_num_mines(&self) -> i32 { self.num_mines }
+}
+
+impl Default for CustomBoard {
+    fn default() -> Self {
+        Self { width: 20, height: 20, num_mines: 60 }
+    }
+}
+
+// Minesweeper settings, shared here so that they can be changed from the menu
+#[derive(Resource, Debug, Clone)]
+pub struct MinesweeperSettings {
+    pub difficulty: Difficulty,
+    pub custom: CustomBoard,
+    pub safe_start: SafeStart,
+    // How the tiles are connected, which decides the numbers and the shape of the board
+    pub topology: Topology,
+    // Whether right clicking a flag turns it into a question mark
+    pub question_marks: bool,
+    // Whether the mines are placed such that the board can be solved without guessing
+    pub no_guess: bool,
+    // Whether triggering a mine can be undone instead of ending the game. Practice games are
+    // never recorded
+    pub practice: bool,
+    // The number of mines that can be stepped on before the game is lost, one for the classic game.
+    // Endless runs don't use it, see lives()
+    pub lives: usize,
+    // Whether to play an endless board, which is generated while it is revealed and ends at the
+    // first mine. The difficulty doesn't apply to it
+    pub endless: bool,
+    // The share of tiles with a mine on an endless board
+    pub density: f64,
+    // Seed for the mine placement, a random one is used for every board if none is given
+    pub seed: Option<u64>,
+}
+
+impl Default for MinesweeperSettings {
+    fn default() -> Self {
+        Self {
+            difficulty: Difficulty::default(),
+            custom: CustomBoard::default(),
+            safe_start: SafeStart::default(),
+            topology: Topology::default(),
+            question_marks: true,
+            no_guess: false,
+            practice: false,
+            lives: 1,
+            endless: false,
+            density: 0.18,
+            seed: None,
+        }
+    }
+}
+
+impl MinesweeperSettings {
+    // The width, height and number of mines of the board for the current difficulty
+    pub fn dimensions(&self) -> (i32, i32, i32) {
+        match self.difficulty {
+            Difficulty::Beginner => (9, 9, 10),
+            Difficulty::Intermediate => (16, 16, 40),
+            Difficulty::Expert => (30, 16, 99),
+            Difficulty::Custom => (self.custom.width, self.custom.height, self.custom.num_mines),
+        }
+    }
+
+    // The number of mines that can be stepped on in a game with these settings. An endless run
+    // always ends at the first mine, so that all runs count towards the same records
+    pub fn lives(&self) -> usize {
+        if self.endless { 1 } else { self.lives.max(1) }
+    }
+
+    // A name for the board of the current difficulty, used to keep records per board. Boards of
+    // another topology than the square one play differently, so they are named after it too, and
+    // so are games with more than one life
+    pub fn board_name(&self) -> String {
+        let name = match (self.endless, self.difficulty) {
+            (true, _) => "Endless".to_string(),
+            (false, Difficulty::Custom) => {
+                let (width, height, num_mines) = self.dimensions();
+                format!("Custom {}x{} {}", width, height, num_mines)
+            },
+            (false, difficulty) => difficulty.name().to_string(),
+        };
+        let name = match self.topology {
+            Topology::Square => name,
+            topology => format!("{} {}", name, topology.name()),
+        };
+        if self.lives() > 1 {
+            format!("{} {} lives", name, self.lives())
+        } else {
+            name
+        }
+    }
+}
+
+// The recorded minesweeper game to watch in the replay viewer
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Default)]
+pub enum MinesweeperReplay {
+    #[default]
+    LastGame,
+    // The best time on the board with the given name
+    BestTime(String),
+}
+
+impl MinesweeperReplay {
+    // The name of the replay file in the minesweeper save folder
+    pub fn file_name(&self) -> String {
+        match self {
+            MinesweeperReplay::LastGame => "replay_last.txt".to_string(),
+            MinesweeperReplay::BestTime(board) => format!("replay_best_{}.txt", board.replace(' ', "_")),
+        }
+    }
+}
+
+// The keys a human player steers their light cycle with
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KeyScheme {
+    Wasd,
+    Arrows,
+    Ijkl,
+}
+
+impl KeyScheme {
+    // Keys in the order Up, Down, Left, Right
+    pub fn keys(&self) -> [KeyCode; 4] {
+        match self {
+            KeyScheme::Wasd => [KeyCode::W, KeyCode::S, KeyCode::A, KeyCode::D],
+            KeyScheme::Arrows => [KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right],
+            KeyScheme::Ijkl => [KeyCode::I, KeyCode::K, KeyCode::J, KeyCode::L],
+        }
+    }
+}
+
+// Who is steering a light cycle
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CycleController {
+    Human(KeyScheme),
+    Ai,
+}
+
+impl CycleController {
+    // The order the menu goes through the controllers of a cycle in
+    pub const ALL: [CycleController; 4] = [
+        CycleController::Human(KeyScheme::Arrows),
+        CycleController::Human(KeyScheme::Wasd),
+        CycleController::Human(KeyScheme::Ijkl),
+        CycleController::Ai,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CycleController::Human(KeyScheme::Arrows) => "Arrows",
+            CycleController::Human(KeyScheme::Wasd) => "WASD",
+            CycleController::Human(KeyScheme::Ijkl) => "IJKL",
+            CycleController::Ai => "AI",
+        }
+    }
+}
+
+// Tron settings, shared here so that they can be changed from the menu: which cycles take part
+// and over how many rounds the match is played
+#[derive(Resource, Debug, Clone)]
+pub struct TronSettings {
+    // One slot per starting position, the empty ones have no cycle
+    slots: [Option<CycleController>; MAX_CYCLES],
+    best_of: u32,
+}
+
+impl TronSettings {
+    pub fn new(controllers: &[CycleController], best_of: u32) -> Result<Self, ArcadeError> {
+        if !(MIN_CYCLES..=MAX_CYCLES).contains(&controllers.len()) {
+            return Err(ArcadeError::new(format!(
+                "Tron needs between {} and {} cycles, got {}",
+                MIN_CYCLES, MAX_CYCLES, controllers.len())));
+        }
+        if !(1..=MAX_TRON_ROUNDS).contains(&best_of) {
+            return Err(ArcadeError::new(format!(
+                "Tron is played over between 1 and {} rounds", MAX_TRON_ROUNDS)));
+        }
+        let mut slots = [None; MAX_CYCLES];
+        for (slot, controller) in slots.iter_mut().zip(controllers) {
+            *slot = Some(*controller);
+        }
+        Ok(Self { slots, best_of })
+    }
+
+    // The controllers of the cycles taking part, in the order of their starting positions
+    pub fn controllers(&self) -> impl Iterator<Item = CycleController> + '_ {
+        self.slots.iter().flatten().copied()
+    }
+
+    pub fn slot(&self, slot: usize) -> Option<CycleController> {
+        self.slots[slot]
+    }
+
+    // Moves the given slot on to the next controller, or leaves it empty after the last one as long
+    // as there are enough cycles left
+    pub fn next_controller(&mut self, slot: usize) {
+        let next = match self.slots[slot] {
+            None => Some(CycleController::ALL[0]),
+            Some(controller) => {
+                let index = CycleController::ALL.iter().position(|c| *c == controller).unwrap_or(0);
+                CycleController::ALL.get(index + 1).copied()
+            },
+        };
+        let cycles = self.controllers().count();
+        self.slots[slot] = match next {
+            None if cycles <= MIN_CYCLES => Some(CycleController::ALL[0]),
+            next => next,
+        };
+    }
+
+    pub fn best_of(&self) -> u32 {
+        self.best_of
+    }
+
+    // Changes the number of rounds by the given amount, keeping it odd so that a match can't tie
+    pub fn change_best_of(&mut self, delta: i32) {
+        self.best_of = (self.best_of as i32 + 2 * delta).clamp(1, MAX_TRON_ROUNDS as i32) as u32;
+    }
+
+    // The number of round wins needed to take the match
+    pub fn wins_needed(&self) -> u32 {
+        self.best_of / 2 + 1
+    }
+}
+
+impl Default for TronSettings {
+    fn default() -> Self {
+        Self {
+            slots: [
+                Some(CycleController::Human(KeyScheme::Arrows)),
+                Some(CycleController::Human(KeyScheme::Wasd)),
+                Some(CycleController::Ai),
+                None,
+            ],
+            best_of: 5,
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+
+use crate::ArcadeError;
+use crate::saves::{read_key_values, write_key_values};
+
+// The scores of a won game of minesweeper which count towards the records
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunScores {
+    pub seconds: f32,
+    // The 3BV of the board, i.e., the least number of clicks needed to clear it, per second
+    pub three_bv_per_second: f32,
+    // The 3BV of the board divided by the number of clicks used, as a percentage
+    pub efficiency: f32,
+}
+
+// Results and records of all games played on one minesweeper board
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BoardStats {
+    pub played: u32,
+    pub won: u32,
+    pub streak: u32,
+    pub best_streak: u32,
+    pub best_time: Option<f32>,
+    pub best_three_bv_per_second: Option<f32>,
+    pub best_efficiency: Option<f32>,
+    // The most tiles revealed in one run of the endless board
+    pub best_score: Option<u32>,
+}
+
+impl BoardStats {
+    // The share of games won as a percentage
+    pub fn win_rate(&self) -> f32 {
+        if self.played == 0 {
+            0.0
+        } else {
+            100.0 * self.won as f32 / self.played as f32
+        }
+    }
+
+    fn set(&mut self, field: &str, value: &str) {
+        match field {
+            "played" => self.played = value.parse().unwrap_or_default(),
+            "won" => self.won = value.parse().unwrap_or_default(),
+            "streak" => self.streak = value.parse().unwrap_or_default(),
+            "best_streak" => self.best_streak = value.parse().unwrap_or_default(),
+            "best_time" => self.best_time = value.parse().ok(),
+            "best_3bv_per_second" => self.best_three_bv_per_second = value.parse().ok(),
+            "best_efficiency" => self.best_efficiency = value.parse().ok(),
+            "best_score" => self.best_score = value.parse().ok(),
+            _ => (),
+        }
+    }
+
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![
+            ("played", self.played.to_string()),
+            ("won", self.won.to_string()),
+            ("streak", self.streak.to_string()),
+            ("best_streak", self.best_streak.to_string()),
+        ];
+        for (field, value) in [
+            ("best_time", self.best_time),
+            ("best_3bv_per_second", self.best_three_bv_per_second),
+            ("best_efficiency", self.best_efficiency),
+        ] {
+            if let Some(value) = value {
+                fields.push((field, value.to_string()));
+            }
+        }
+        if let Some(best_score) = self.best_score {
+            fields.push(("best_score", best_score.to_string()));
+        }
+        fields
+    }
+}
+
+// The stats of every minesweeper board played, stored as `board.field=value` lines in the
+// minesweeper save folder
+#[derive(Debug, Clone, Default)]
+pub struct MinesweeperStats {
+    boards: BTreeMap<String, BoardStats>,
+}
+
+impl MinesweeperStats {
+    const GAME: &'static str = "minesweeper";
+    const FILE: &'static str = "stats.txt";
+
+    pub fn load() -> Result<Self, ArcadeError> {
+        let mut boards: BTreeMap<String, BoardStats> = BTreeMap::new();
+        for (key, value) in read_key_values(Self::GAME, Self::FILE)? {
+            if let Some((board, field)) = key.rsplit_once('.') {
+                boards.entry(board.to_string()).or_default().set(field, &value);
+            }
+        }
+        Ok(Self { boards })
+    }
+
+    pub fn save(&self) -> Result<(), ArcadeError> {
+        let values: BTreeMap<String, String> = self.boards.iter()
+            .flat_map(|(board, stats)| stats.fields()
+                .into_iter()
+                .map(move |(field, value)| (format!("{}.{}", board, field), value)))
+            .collect();
+        write_key_values(Self::GAME, Self::FILE, &values)
+    }
+
+    pub fn board(&self, board: &str) -> BoardStats {
+        self.boards.get(board).copied().unwrap_or_default()
+    }
+
+    // All boards that have been played, in order of their name
+    pub fn boards(&self) -> impl Iterator<Item = (&String, &BoardStats)> {
+        self.boards.iter()
+    }
+
+    pub fn record_loss(&mut self, board: &str) {
+        let stats = self.boards.entry(board.to_string()).or_default();
+        stats.played += 1;
+        stats.streak = 0;
+    }
+
+    // Records a win, with the scores if the run counts towards the records. Returns whether the
+    // run set a new best time
+    pub fn record_win(&mut self, board: &str, scores: Option<RunScores>) -> bool {
+        let stats = self.boards.entry(board.to_string()).or_default();
+        stats.played += 1;
+        stats.won += 1;
+        stats.streak += 1;
+        stats.best_streak = stats.best_streak.max(stats.streak);
+
+        let Some(scores) = scores else { return false };
+        let is_best_time = stats.best_time.map_or(true, |best| scores.seconds < best);
+        if is_best_time {
+            stats.best_time = Some(scores.seconds);
+        }
+        stats.best_three_bv_per_second = Some(stats.best_three_bv_per_second
+            .map_or(scores.three_bv_per_second, |best| best.max(scores.three_bv_per_second)));
+        stats.best_efficiency = Some(stats.best_efficiency
+            .map_or(scores.efficiency, |best| best.max(scores.efficiency)));
+        is_best_time
+    }
+
+    // Records a run of the endless board, which always ends on a mine. Returns whether the run
+    // set a new best score
+    pub fn record_run(&mut self, board: &str, score: u32) -> bool {
+        let stats = self.boards.entry(board.to_string()).or_default();
+        stats.played += 1;
+        let is_best_score = stats.best_score.map_or(true, |best| score > best);
+        if is_best_score {
+            stats.best_score = Some(score);
+        }
+        is_best_score
+    }
+}
@@ -8,6 +8,9 @@ pub enum ArcadeState {
     Popup,
     PlayingSnake,
     PlayingMinesweeper,
+    ReplayingMinesweeper,
+    PlayingMinesweeperVersus,
+    PlayingTron,
 }
 
 // Status
@@ -16,6 +19,8 @@ pub enum ActiveGameState {
     #[default]
     Snake,
     Minesweeper,
+    MinesweeperVersus,
+    Tron,
 }
 
 impl ActiveGameState {
@@ -23,6 +28,8 @@ impl ActiveGameState {
         match self {
             ActiveGameState::Snake       => ArcadeState::PlayingSnake,
             ActiveGameState::Minesweeper => ArcadeState::PlayingMinesweeper,
+            ActiveGameState::MinesweeperVersus => ArcadeState::PlayingMinesweeperVersus,
+            ActiveGameState::Tron        => ArcadeState::PlayingTron,
         }
     }
 }
@@ -31,6 +38,12 @@ impl ActiveGameState {
 #[derive(Debug)]
 pub struct ArcadeError(String);
 
+impl ArcadeError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self(message.into())
+    }
+}
+
 impl std::error::Error for ArcadeError {}
 
 impl std::fmt::Display for ArcadeError {
@@ -38,3 +51,9 @@ impl std::fmt::Display for ArcadeError {
         write!(f, "{}", self.0)
     }
 }
+
+impl From<std::io::Error> for ArcadeError {
+    fn from(error: std::io::Error) -> Self {
+        Self(error.to_string())
+    }
+}
@@ -0,0 +1,154 @@
+use crate::Coord2D;
+
+// The cells around a cell of a square grid
+const SQUARE_OFFSETS: [(i32, i32); 8] = [
+    (-1, -1), (0, -1), (1, -1),
+    (-1, 0), (1, 0),
+    (-1, 1), (0, 1), (1, 1),
+];
+
+// The cells around a cell of a hexagonal grid, laid out in rows of which the odd ones are shifted
+// half a cell to the right. Which cells above and below touch a cell depends on its row
+const HEX_EVEN_ROW_OFFSETS: [(i32, i32); 6] = [(-1, -1), (0, -1), (-1, 0), (1, 0), (-1, 1), (0, 1)];
+const HEX_ODD_ROW_OFFSETS: [(i32, i32); 6] = [(0, -1), (1, -1), (-1, 0), (1, 0), (0, 1), (1, 1)];
+
+// How the cells of a grid board are connected, which decides the neighbors of a cell and where
+// the board ends
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Topology {
+    // Square cells with eight neighbors, up to the edges of the board
+    #[default]
+    Square,
+    // Square cells on a board of which the opposite edges are joined, so that it has no edges
+    Toroidal,
+    // Hexagonal cells with six neighbors, in staggered rows
+    Hexagonal,
+}
+
+impl Topology {
+    pub const ALL: [Topology; 3] = [Topology::Square, Topology::Toroidal, Topology::Hexagonal];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Topology::Square => "Square",
+            Topology::Toroidal => "Toroidal",
+            Topology::Hexagonal => "Hexagonal",
+        }
+    }
+
+    // The topology with the given name, in any case
+    pub fn from_name(name: &str) -> Option<Self> {
+        Topology::ALL.into_iter().find(|topology| topology.name().eq_ignore_ascii_case(name))
+    }
+
+    // Whether the rows are shifted against each other, by half a cell on every other row
+    pub fn is_staggered(&self) -> bool {
+        *self == Topology::Hexagonal
+    }
+
+    // The cell a coordinate ends up at on a board of the given width and height, if any. A torus
+    // wraps coordinates off one edge around to the opposite one
+    pub fn place(&self, coord: Coord2D<i32>, (width, height): (i32, i32)) -> Option<Coord2D<i32>> {
+        match self {
+            Topology::Toroidal => Some(Coord2D(coord.0.rem_euclid(width), coord.1.rem_euclid(height))),
+            Topology::Square | Topology::Hexagonal => {
+                let on_board = coord.0 >= 0 && coord.0 < width && coord.1 >= 0 && coord.1 < height;
+                on_board.then_some(coord)
+            },
+        }
+    }
+
+    // The cells around the given one, on a board of the given size if it is bounded. On a torus
+    // narrower than three cells the same cell can be reached from two sides, it is only counted
+    // once, and a cell is never its own neighbor. This is called for every tile of a flood, so
+    // the cells are worked out as they are taken rather than collected
+    pub fn neighbors(
+        &self,
+        bounds: Option<(i32, i32)>,
+        coord: Coord2D<i32>,
+    ) -> impl Iterator<Item = Coord2D<i32>> {
+        let topology = *self;
+        let offsets: &'static [(i32, i32)] = match topology {
+            Topology::Square | Topology::Toroidal => &SQUARE_OFFSETS,
+            Topology::Hexagonal if coord.1.rem_euclid(2) == 0 => &HEX_EVEN_ROW_OFFSETS,
+            Topology::Hexagonal => &HEX_ODD_ROW_OFFSETS,
+        };
+        let narrow_torus = topology == Topology::Toroidal
+            && bounds.is_some_and(|(width, height)| width < 3 || height < 3);
+        let place = move |(dx, dy): (i32, i32)| {
+            let neighbor = Coord2D(coord.0 + dx, coord.1 + dy);
+            match bounds {
+                Some(bounds) => topology.place(neighbor, bounds),
+                None => Some(neighbor),
+            }
+        };
+
+        offsets.iter().enumerate().filter_map(move |(i, &offset)| {
+            let neighbor = place(offset).filter(|neighbor| *neighbor != coord)?;
+            let seen_before = narrow_torus
+                && offsets[..i].iter().any(|&earlier| place(earlier) == Some(neighbor));
+            (!seen_before).then_some(neighbor)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn neighbors(
+        topology: Topology,
+        bounds: Option<(i32, i32)>,
+        coord: Coord2D<i32>,
+    ) -> Vec<Coord2D<i32>> {
+        topology.neighbors(bounds, coord).collect()
+    }
+
+    #[test]
+    fn square_neighbors_stop_at_the_edges() {
+        assert_eq!(neighbors(Topology::Square, Some((5, 5)), Coord2D(2, 2)).len(), 8);
+        assert_eq!(
+            neighbors(Topology::Square, Some((5, 5)), Coord2D(0, 0)),
+            vec![Coord2D(1, 0), Coord2D(0, 1), Coord2D(1, 1)]);
+        assert_eq!(neighbors(Topology::Square, None, Coord2D(0, 0)).len(), 8);
+    }
+
+    #[test]
+    fn torus_neighbors_wrap_around() {
+        let corner = neighbors(Topology::Toroidal, Some((5, 5)), Coord2D(0, 0));
+        assert_eq!(corner.len(), 8);
+        assert!(corner.contains(&Coord2D(4, 4)));
+    }
+
+    #[test]
+    fn narrow_torus_counts_every_neighbor_once() {
+        let cases = [((2, 5), 5), ((5, 2), 5), ((2, 2), 3), ((1, 5), 2), ((1, 1), 0)];
+        for (bounds, expected) in cases {
+            let found = neighbors(Topology::Toroidal, Some(bounds), Coord2D(0, 0));
+            let unique: HashSet<_> = found.iter().copied().collect();
+            assert_eq!(found.len(), unique.len(), "{:?}", bounds);
+            assert_eq!(found.len(), expected, "{:?}", bounds);
+            assert!(!unique.contains(&Coord2D(0, 0)));
+        }
+    }
+
+    #[test]
+    fn hexagonal_neighbors_follow_the_row() {
+        assert_eq!(
+            neighbors(Topology::Hexagonal, None, Coord2D(2, 2)),
+            vec![
+                Coord2D(1, 1), Coord2D(2, 1),
+                Coord2D(1, 2), Coord2D(3, 2),
+                Coord2D(1, 3), Coord2D(2, 3),
+            ]);
+        assert_eq!(
+            neighbors(Topology::Hexagonal, None, Coord2D(2, 1)),
+            vec![
+                Coord2D(2, 0), Coord2D(3, 0),
+                Coord2D(1, 1), Coord2D(3, 1),
+                Coord2D(2, 2), Coord2D(3, 2),
+            ]);
+    }
+}
@@ -0,0 +1,42 @@
+// Benchmarks of revealing on large boards with few mines, where a single reveal floods most of
+// the board. Run with `cargo bench -p minesweeper`
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+use arcade_util::{Coord2D, SafeStart};
+use minesweeper::Minefield;
+
+const SIZE: i32 = 500;
+const SEED: u64 = 0;
+
+fn sparse_minefield(density: f32) -> Minefield {
+    let num_mines = (density * (SIZE * SIZE) as f32) as i32;
+    Minefield::build(SIZE, SIZE, num_mines, SafeStart::Opening, SEED)
+}
+
+fn reveal(c: &mut Criterion) {
+    let mut group = c.benchmark_group("reveal 500x500");
+    group.sample_size(10);
+
+    for density in [0.001, 0.01, 0.05] {
+        // The first reveal places the mines as well
+        group.bench_function(format!("first reveal, {}% mines", density * 100.0), |b| {
+            b.iter_batched(
+                || sparse_minefield(density),
+                |mut minefield| minefield.reveal_coord(&Coord2D(SIZE / 2, SIZE / 2)),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn game_over(c: &mut Criterion) {
+    let mut minefield = sparse_minefield(0.01);
+    minefield.reveal_coord(&Coord2D(SIZE / 2, SIZE / 2));
+
+    c.bench_function("game over 500x500, 1% mines", |b| b.iter(|| minefield.game_over()));
+}
+
+criterion_group!(benches, reveal, game_over);
+criterion_main!(benches);
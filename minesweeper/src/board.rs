@@ -1,17 +1,33 @@
 use std::ops::Deref;
 
-use arcade_util::{DiscreteBoard, Coord2D};
-use bevy::prelude::{Component, Commands, Color, Vec2, default, BuildChildren, Transform};
-use bevy::sprite::{SpriteBundle, Sprite};
+use arcade_util::{DiscreteBoard, FitCameraQuery, fit_camera};
+use bevy::prelude::{
+    Component,
+    Commands,
+    Color,
+    default,
+    Transform,
+    Query,
+    Res,
+    SpatialBundle,
+    Entity,
+    BuildChildren,
+};
 use bevy::window::Window;
+use bevy::sprite::{SpriteBundle, Sprite};
 
-use crate::util::{BOARD_SIZE, TILE_SIZE, TILE_SPACING, TILE_COLOR_COVERED, Cover};
+use crate::minefield::Minefield;
+use crate::tile_view::{TileImages, spawn_tile_views};
+use crate::util::{TILE_SIZE, TILE_SPACING};
 
 // this file should contain the MinesweeperBoard struct and its associated impl block. Also
 // constructing the board component through the spawn_board system.
 
 const BOARD_COLOR: Color = Color::rgb(0.2, 0.2, 0.2);
 
+// Room left around the board when fitting it in the window, as high as the HUD
+pub const BOARD_MARGIN: f32 = 80.0;
+
 #[derive(Component)]
 pub struct MinesweeperBoard(DiscreteBoard);
 
@@ -23,58 +39,67 @@ impl Deref for MinesweeperBoard {
     }
 }
 
-impl MinesweeperBoard {
-    fn inside_board(&self, position: Vec2) -> bool {
-        let board_size = self.get_physical_size();
-        position.x >= -board_size / 2. && position.x <= board_size / 2. &&
-            position.y >= -board_size / 2. && position.y <= board_size / 2.
-    }
-
-    pub fn mouse_to_coord(&self, window: &Window, mouse_position: Vec2) -> Option<Coord2D<i32>> {
-        // Window to world space
-        let window_size = Vec2::new(window.width(), window.height());
-        let mouse_position = mouse_position - window_size / 2.;
-
-        // Bounds check
-        if !self.inside_board(mouse_position) {
-            return None;
-        }
+// This function spawns the board component, sized and centered for the minefield, with a view for
+// every tile on it
+pub fn spawn_board(
+    mut commands: Commands,
+    minefield: Res<Minefield>,
+    images: Res<TileImages>,
+) {
+    spawn_board_entities(&mut commands, &minefield, &images);
+}
 
-        // World space to board space
-        Some(
-            Coord2D(
-                self.physical_pos_to_cell_pos(mouse_position.x),
-                self.physical_pos_to_cell_pos(mouse_position.y)
-            )
-        )
+// Zooms the camera out as far as needed for the whole board to fit below the HUD
+pub fn fit_camera_to_board(
+    windows: Query<&Window>,
+    board: Query<&MinesweeperBoard>,
+    mut camera: Query<FitCameraQuery>,
+) {
+    let (Ok(window), Ok(board)) = (windows.get_single(), board.get_single()) else { return };
+    for (mut camera, mut transform, mut projection) in camera.iter_mut() {
+        fit_camera(
+            window,
+            board.get_physical_size(),
+            BOARD_MARGIN,
+            (&mut camera, &mut transform, &mut projection));
     }
 }
 
-// This function spawns the board component and returns the entity id
-pub fn spawn_board(mut commands: Commands) {
-    let board = MinesweeperBoard(DiscreteBoard::new(BOARD_SIZE, TILE_SIZE, TILE_SPACING));
-    let board_copy = MinesweeperBoard(DiscreteBoard::new(BOARD_SIZE, TILE_SIZE, TILE_SPACING));
+// Spawns the board with its tile views as children, and returns the board entity. The views follow
+// the minefield, so that a board can also be spawned for a game in progress. The board is laid out
+// for the topology of the minefield. An endless board has no background, and its tile views are
+// spawned as they come into view
+pub fn spawn_board_entities(
+    commands: &mut Commands,
+    minefield: &Minefield,
+    images: &TileImages,
+) -> Entity {
+    if minefield.is_endless() {
+        let board = DiscreteBoard::unbounded(TILE_SIZE, TILE_SPACING)
+            .with_topology(minefield.topology());
+        return commands.spawn(MinesweeperBoard(board))
+            .insert(SpatialBundle::default())
+            .id();
+    }
+
+    let board = MinesweeperBoard(DiscreteBoard::rectangular(
+        minefield.width(),
+        minefield.height(),
+        TILE_SIZE,
+        TILE_SPACING)
+        .with_topology(minefield.topology()));
     let ps = board.get_physical_size();
 
-    commands.spawn(board)
-        .insert(SpriteBundle {
-            sprite: Sprite {
-                color: BOARD_COLOR,
-                custom_size: Some(Vec2::splat(ps)),
-                ..default()
-            },
-            transform: Transform::from_xyz(0.0, 0.0, 0.0),
+    commands.spawn(SpriteBundle {
+        sprite: Sprite {
+            color: BOARD_COLOR,
+            custom_size: Some(ps),
             ..default()
-        })
-        .with_children(|parent| {
-            for x in 0..BOARD_SIZE {
-                for y in 0..BOARD_SIZE {
-                    parent.spawn(Cover::Unflagged)
-                        .insert(
-                            board_copy.tile_sprite_at_coord(x, y, 3, TILE_COLOR_COVERED)
-                        )
-                        .insert(Coord2D(x, y));
-                }
-            }
-        });
+        },
+        transform: Transform::from_xyz(0.0, 0.0, 0.0),
+        ..default()
+    })
+    .with_children(|builder| spawn_tile_views(builder, &board, images))
+    .insert(board)
+    .id()
 }
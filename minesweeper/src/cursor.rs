@@ -0,0 +1,193 @@
+// This file contains keyboard play for minesweeper. A cursor is moved over the board with the arrow
+// keys or the vim keys, like in snake, and the tile under it is played with the action keys.
+
+use bevy::prelude::{
+    Commands,
+    Component,
+    Resource,
+    Res,
+    ResMut,
+    Query,
+    With,
+    Input,
+    KeyCode,
+    Color,
+    EventWriter,
+    Transform,
+    GlobalTransform,
+    Camera,
+    Vec2,
+    Visibility,
+    DetectChanges,
+    Entity,
+    Parent,
+    BuildChildren,
+};
+
+use arcade_util::{
+    ArcadeCamera,
+    Coord2D,
+    Dir2D,
+    PAN_MODIFIERS,
+    visible_world_rect,
+};
+
+use crate::board::MinesweeperBoard;
+use crate::util::{MinesweeperAction, HoveredCell, TILE_SIZE};
+
+pub const REVEAL_KEY: KeyCode = KeyCode::Space;
+pub const FLAG_KEY: KeyCode = KeyCode::F;
+pub const CHORD_KEY: KeyCode = KeyCode::D;
+
+const CURSOR_COLOR: Color = Color::rgba(1.0, 0.85, 0.0, 0.4);
+
+// Drawn above the covers, but below the hints
+const CURSOR_Z: i32 = 8;
+
+// The tile the keyboard actions are played on, and whether it is highlighted
+#[derive(Resource)]
+pub struct MinesweeperCursor {
+    pub coord: Coord2D<i32>,
+    pub shown: bool,
+}
+
+impl Default for MinesweeperCursor {
+    fn default() -> Self {
+        MinesweeperCursor { coord: Coord2D(0, 0), shown: false }
+    }
+}
+
+// Tag component for the highlighted tile under the cursor
+#[derive(Component)]
+pub struct CursorHighlight;
+
+// Place the cursor in the middle of the board. The highlight stays hidden until the keyboard is
+// used, so that it doesn't get in the way of playing with the mouse
+pub fn spawn_cursor(
+    mut commands: Commands,
+    mut cursor: ResMut<MinesweeperCursor>,
+    board: Query<(Entity, &MinesweeperBoard)>,
+) {
+    let (entity, board) = board.single();
+    spawn_cursor_on(&mut commands, &mut cursor, entity, board);
+}
+
+// Places the cursor in the middle of the given board, with the highlight as a child of the board
+pub fn spawn_cursor_on(
+    commands: &mut Commands,
+    cursor: &mut MinesweeperCursor,
+    board_entity: Entity,
+    board: &MinesweeperBoard,
+) {
+    *cursor = MinesweeperCursor {
+        coord: Coord2D(board.get_width() / 2, board.get_height() / 2),
+        shown: false,
+    };
+
+    commands.entity(board_entity).with_children(|builder| {
+        builder.spawn(board.tile_sprite_at_coord(cursor.coord.0, cursor.coord.1, CURSOR_Z, CURSOR_COLOR))
+            .insert(Visibility::Hidden)
+            .insert(CursorHighlight);
+    });
+}
+
+pub fn keyboard_actions(
+    keys: Res<Input<KeyCode>>,
+    mut cursor: ResMut<MinesweeperCursor>,
+    board: Query<&MinesweeperBoard>,
+    mut hovered: ResMut<HoveredCell>,
+    mut actions: EventWriter<MinesweeperAction>,
+) {
+    let board = board.single();
+    let used = cursor_actions(&keys, &mut cursor, board, |action| actions.send(action));
+
+    if used && hovered.0 != Some(cursor.coord) {
+        hovered.0 = Some(cursor.coord);
+    }
+}
+
+// Moves the cursor with the keys pressed this frame and plays the tile under it. Returns whether
+// any of the keys were used, after which the cursor is shown. On a torus the cursor wraps around
+// the edges. The cursor is only changed when it moves or is first shown, so that the camera follows
+// it only then
+pub fn cursor_actions(
+    keys: &Input<KeyCode>,
+    cursor: &mut ResMut<MinesweeperCursor>,
+    board: &MinesweeperBoard,
+    mut play: impl FnMut(MinesweeperAction),
+) -> bool {
+    let mut used = false;
+
+    for (keys_for_direction, direction) in [
+        ([KeyCode::H, KeyCode::Left], Dir2D::Left),
+        ([KeyCode::J, KeyCode::Down], Dir2D::Down),
+        ([KeyCode::K, KeyCode::Up], Dir2D::Up),
+        ([KeyCode::L, KeyCode::Right], Dir2D::Right),
+    ] {
+        // The arrow keys pan the camera while a pan modifier is held
+        if keys.any_just_pressed(keys_for_direction) && !keys.any_pressed(PAN_MODIFIERS) {
+            used = true;
+            if let Some(moved) = board.place_coord(cursor.coord + direction.as_coord()) {
+                cursor.coord = moved;
+            }
+        }
+    }
+
+    for (key, action) in [
+        (REVEAL_KEY, MinesweeperAction::Reveal(cursor.coord)),
+        (FLAG_KEY, MinesweeperAction::CycleCover(cursor.coord)),
+        (CHORD_KEY, MinesweeperAction::Chord(cursor.coord)),
+    ] {
+        if keys.just_pressed(key) {
+            used = true;
+            play(action);
+        }
+    }
+
+    if used && !cursor.shown {
+        cursor.shown = true;
+    }
+    used
+}
+
+// Moves the highlight to the cursor and shows it once the cursor is used
+pub fn sync_cursor_highlight(
+    cursor: Res<MinesweeperCursor>,
+    boards: Query<&MinesweeperBoard>,
+    mut highlight: Query<(&mut Transform, &mut Visibility, &Parent), With<CursorHighlight>>,
+) {
+    if !cursor.is_changed() {
+        return;
+    }
+
+    for (mut transform, mut visibility, parent) in highlight.iter_mut() {
+        let Ok(board) = boards.get(parent.get()) else { continue };
+        let position = board.cell_pos_to_physical_pos(cursor.coord);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        *visibility = if cursor.shown { Visibility::Visible } else { Visibility::Hidden };
+    }
+}
+
+// Pans the camera to the cursor when the keyboard moves it out of view, such that it can be used
+// all over boards larger than the window
+pub fn follow_cursor(
+    cursor: Res<MinesweeperCursor>,
+    board: Query<(&MinesweeperBoard, &GlobalTransform)>,
+    mut camera: Query<(&Camera, &GlobalTransform, &mut Transform), With<ArcadeCamera>>,
+) {
+    if !cursor.is_changed() || !cursor.shown {
+        return;
+    }
+    let Ok((board, board_transform)) = board.get_single() else { return };
+    let Ok((camera, camera_transform, mut transform)) = camera.get_single_mut() else { return };
+    let Some(view) = visible_world_rect(camera, camera_transform) else { return };
+
+    // Keep a tile of room between the cursor and the edge of the view
+    let position = board_transform.translation().truncate()
+        + board.cell_pos_to_physical_pos(cursor.coord);
+    let room = Vec2::splat(2.0 * TILE_SIZE).min(view.half_size());
+    let shift = (position + room - view.max).max(Vec2::ZERO)
+        + (position - room - view.min).min(Vec2::ZERO);
+    transform.translation += shift.extend(0.0);
+}
@@ -0,0 +1,67 @@
+// This file contains the explosion shown on a mine when it is stepped on. It bursts out of the tile
+// and fades away, after which it is despawned.
+
+use bevy::prelude::{
+    Commands,
+    Component,
+    Entity,
+    Res,
+    Query,
+    Color,
+    Transform,
+    Vec2,
+    Vec3,
+    Quat,
+    DespawnRecursiveExt,
+    default,
+};
+use bevy::sprite::{Sprite, SpriteBundle};
+use bevy::time::{Time, Timer, TimerMode};
+
+use crate::util::TILE_SIZE;
+
+const EXPLOSION_COLOR: Color = Color::rgba(1.0, 0.6, 0.0, 0.9);
+const EXPLOSION_SECONDS: f32 = 0.6;
+// How many times the size of a tile the explosion grows to
+const EXPLOSION_SCALE: f32 = 2.5;
+
+// Drawn above the icon and number of the tile it is on
+const EXPLOSION_Z: f32 = 5.0;
+
+// An explosion, which goes on until its timer finishes
+#[derive(Component)]
+pub struct Explosion(Timer);
+
+// The explosion to spawn as a child of the tile view of a mine
+pub fn explosion() -> (SpriteBundle, Explosion) {
+    (
+        SpriteBundle {
+            sprite: Sprite {
+                color: EXPLOSION_COLOR,
+                custom_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, 0.0, EXPLOSION_Z),
+            ..default()
+        },
+        Explosion(Timer::from_seconds(EXPLOSION_SECONDS, TimerMode::Once)),
+    )
+}
+
+// Grows and turns the explosions while fading them out, and despawns the ones that are done
+pub fn animate_explosions(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut explosions: Query<(Entity, &mut Explosion, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, mut explosion, mut transform, mut sprite) in explosions.iter_mut() {
+        if explosion.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let progress = explosion.0.percent();
+        transform.scale = Vec3::splat(1.0 + (EXPLOSION_SCALE - 1.0) * progress);
+        transform.rotation = Quat::from_rotation_z(progress * std::f32::consts::FRAC_PI_2);
+        sprite.color.set_a(EXPLOSION_COLOR.a() * (1.0 - progress));
+    }
+}
@@ -0,0 +1,327 @@
+// This file contains the import and export of minesweeper boards, so that exact puzzles can be
+// shared and a game can be reproduced.
+//
+// The text format starts with a few `key=value` header lines, followed by one line of tiles per
+// row, with the top row of the board first. Lines starting with `#` are comments.
+//
+//     width=5
+//     height=3
+//     mines=3
+//     *..o.
+//     .F.o?
+//     ....*
+//
+// Boards of another topology than the square one say so with a `topology=toroidal` or
+// `topology=hexagonal` header line. On a hexagonal board the odd rows, counted from the bottom
+// row, are shifted half a tile to the right. A game with more than one life has a `lives=3`
+// header line.
+//
+// Every tile is one character, a mine is a `*` and a safe tile a `.`. When the state of the game
+// is included, revealed tiles are `o` (safe) or `X` (mine), flags are `F` (on a mine) or `f` (on a
+// safe tile) and question marks are `Q` (on a mine) or `?` (on a safe tile).
+//
+// Two community formats are supported as well: plain grids of `*` and `.` (or digits) without a
+// header, and the binary MBF format of the minesweeper arbiter and viennasweeper programs.
+
+use arcade_util::{ArcadeError, Coord2D, Topology};
+
+use crate::minefield::Minefield;
+use crate::util::Cover;
+
+// The largest board side which fits in a MBF file
+const MBF_MAX_SIDE: i32 = u8::MAX as i32;
+
+// The layout and state of a single tile as written in the text format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TileState {
+    mine: bool,
+    revealed: bool,
+    cover: Cover,
+}
+
+impl TileState {
+    fn to_char(self) -> char {
+        match (self.mine, self.revealed, self.cover) {
+            (true, true, _) => 'X',
+            (false, true, _) => 'o',
+            (true, false, Cover::Flagged) => 'F',
+            (false, false, Cover::Flagged) => 'f',
+            (true, false, Cover::Questioned) => 'Q',
+            (false, false, Cover::Questioned) => '?',
+            (true, false, Cover::Unflagged) => '*',
+            (false, false, Cover::Unflagged) => '.',
+        }
+    }
+
+    // Digits are accepted as safe tiles, since some community grids write out the numbers
+    fn from_char(c: char) -> Option<Self> {
+        let (mine, revealed, cover) = match c {
+            'X' => (true, true, Cover::Unflagged),
+            'o' => (false, true, Cover::Unflagged),
+            'F' => (true, false, Cover::Flagged),
+            'f' => (false, false, Cover::Flagged),
+            'Q' => (true, false, Cover::Questioned),
+            '?' => (false, false, Cover::Questioned),
+            '*' => (true, false, Cover::Unflagged),
+            '.' | '0'..='8' => (false, false, Cover::Unflagged),
+            _ => return None,
+        };
+        Some(TileState { mine, revealed, cover })
+    }
+}
+
+// Write the minefield in the text format, optionally with the revealed tiles, flags and question
+// marks
+pub fn to_text(minefield: &Minefield, include_state: bool) -> String {
+    let mut text = format!(
+        "width={}\nheight={}\nmines={}\n",
+        minefield.width(),
+        minefield.height(),
+        minefield.num_mines());
+    if minefield.topology() != Topology::Square {
+        text.push_str(&format!("topology={}\n", minefield.topology().name().to_lowercase()));
+    }
+    if let Some(lives) = minefield.lives().filter(|lives| *lives > 1) {
+        text.push_str(&format!("lives={}\n", lives));
+    }
+
+    for y in (0..minefield.height()).rev() {
+        for x in 0..minefield.width() {
+            let coord = Coord2D(x, y);
+            let tile = TileState {
+                mine: minefield.is_mine(&coord),
+                revealed: include_state && minefield.is_revealed(&coord),
+                cover: if include_state { minefield.cover(&coord) } else { Cover::Unflagged },
+            };
+            text.push(tile.to_char());
+        }
+        text.push('\n');
+    }
+    text
+}
+
+// Read a minefield from the text format or a plain grid. A header is optional, but has to match
+// the grid when it is given
+pub fn from_text(text: &str) -> Result<Minefield, ArcadeError> {
+    let mut header: Vec<(usize, &str, &str)> = Vec::new();
+    let mut rows: Vec<Vec<TileState>> = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some((key, value)) = line.split_once('=') {
+            header.push((number, key.trim(), value.trim()));
+            continue;
+        }
+
+        let row = line.chars()
+            .map(|c| TileState::from_char(c).ok_or_else(|| ArcadeError::new(format!(
+                "Line {}: unknown tile '{}'", number + 1, c))))
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.push(row);
+    }
+
+    let height = rows.len() as i32;
+    let width = rows.first().map_or(0, |row| row.len() as i32);
+    if rows.iter().any(|row| row.len() as i32 != width) {
+        return Err(ArcadeError::new("All rows of the board must be equally long"));
+    }
+
+    // The top row comes first, which is the highest y coordinate on the board
+    let tiles: Vec<(Coord2D<i32>, TileState)> = rows.iter()
+        .enumerate()
+        .flat_map(|(row, tiles)| tiles.iter()
+            .enumerate()
+            .map(move |(x, tile)| (Coord2D(x as i32, height - 1 - row as i32), *tile)))
+        .collect();
+    let mines: Vec<Coord2D<i32>> = tiles.iter()
+        .filter(|(_, tile)| tile.mine)
+        .map(|(coord, _)| *coord)
+        .collect();
+
+    let mut topology = Topology::Square;
+    let mut lives = 1;
+    for (number, key, value) in header {
+        let actual = match key {
+            "width" => width,
+            "height" => height,
+            "mines" => mines.len() as i32,
+            "topology" => {
+                topology = Topology::from_name(value).ok_or_else(|| ArcadeError::new(format!(
+                    "Line {}: unknown topology {}", number + 1, value)))?;
+                continue;
+            },
+            "lives" => {
+                lives = value.parse().ok().filter(|lives| *lives >= 1).ok_or_else(|| ArcadeError::new(
+                    format!("Line {}: {} is not a number of lives", number + 1, value)))?;
+                continue;
+            },
+            // Unknown keys are skipped, so that files from newer versions can still be read
+            _ => continue,
+        };
+        let value: i32 = value.parse().map_err(|_| ArcadeError::new(format!(
+            "Line {}: {} is not a number", number + 1, value)))?;
+        if value != actual {
+            return Err(ArcadeError::new(format!(
+                "The header says {}={}, but the board has {}", key, value, actual)));
+        }
+    }
+
+    let mut minefield = Minefield::from_mines(width, height, topology, &mines)?.with_lives(lives);
+    for (coord, tile) in tiles.iter() {
+        minefield.restore_tile(coord, tile.revealed, tile.cover);
+    }
+    Ok(minefield)
+}
+
+// Write the mines in the MBF format: the width and height as bytes, the number of mines as a big
+// endian u16 and then the x and y of every mine as bytes, counting y from the top row. The format
+// has no room for a topology, so only square boards can be written
+pub fn to_mbf(minefield: &Minefield) -> Result<Vec<u8>, ArcadeError> {
+    if minefield.topology() != Topology::Square {
+        return Err(ArcadeError::new("MBF files only fit boards of square tiles"));
+    }
+    if minefield.width() > MBF_MAX_SIDE || minefield.height() > MBF_MAX_SIDE {
+        return Err(ArcadeError::new(format!(
+            "MBF files only fit boards of up to {0}x{0}", MBF_MAX_SIDE)));
+    }
+    let num_mines = u16::try_from(minefield.num_mines())
+        .map_err(|_| ArcadeError::new("MBF files only fit up to 65535 mines"))?;
+
+    let mut mines: Vec<Coord2D<i32>> = minefield.coords()
+        .filter(|coord| minefield.is_mine(coord))
+        .collect();
+    mines.sort_by_key(|coord| (minefield.height() - 1 - coord.1, coord.0));
+
+    let mut bytes = vec![minefield.width() as u8, minefield.height() as u8];
+    bytes.extend(num_mines.to_be_bytes());
+    for coord in mines {
+        bytes.push(coord.0 as u8);
+        bytes.push((minefield.height() - 1 - coord.1) as u8);
+    }
+    Ok(bytes)
+}
+
+// Read a minefield from the MBF format
+pub fn from_mbf(bytes: &[u8]) -> Result<Minefield, ArcadeError> {
+    let [width, height, high, low, positions @ ..] = bytes else {
+        return Err(ArcadeError::new("A MBF file starts with a 4 byte header"));
+    };
+    let (width, height) = (*width as i32, *height as i32);
+    let num_mines = u16::from_be_bytes([*high, *low]) as usize;
+    if positions.len() != 2 * num_mines {
+        return Err(ArcadeError::new(format!(
+            "The MBF header says {} mines, but the file has room for {}",
+            num_mines, positions.len() / 2)));
+    }
+
+    let mines: Vec<Coord2D<i32>> = positions.chunks(2)
+        .map(|position| Coord2D(position[0] as i32, height - 1 - position[1] as i32))
+        .collect();
+    Minefield::from_mines(width, height, Topology::Square, &mines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::MinesweeperStatus;
+
+    const BOARD: &str = "\
+width=5
+height=3
+mines=3
+*..o.
+.F.o?
+....*
+";
+
+    fn mines(minefield: &Minefield) -> Vec<Coord2D<i32>> {
+        let mut mines: Vec<_> = minefield.coords().filter(|coord| minefield.is_mine(coord)).collect();
+        mines.sort_by_key(|coord| (coord.1, coord.0));
+        mines
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let minefield = from_text(BOARD).unwrap();
+        assert_eq!(mines(&minefield), vec![Coord2D(4, 0), Coord2D(1, 1), Coord2D(0, 2)]);
+        assert_eq!(to_text(&minefield, true), BOARD);
+        assert_eq!(to_text(&from_text(&to_text(&minefield, false)).unwrap(), true), "\
+width=5
+height=3
+mines=3
+*....
+.*...
+....*
+");
+    }
+
+    #[test]
+    fn text_round_trip_keeps_topology_and_lives() {
+        let text = "width=4\nheight=2\nmines=1\ntopology=hexagonal\nlives=3\n.X..\n.oo.\n";
+        let minefield = from_text(text).unwrap();
+        assert_eq!(minefield.topology(), Topology::Hexagonal);
+        assert_eq!(minefield.lives_left(), Some(2));
+        assert_eq!(to_text(&minefield, true), text);
+    }
+
+    #[test]
+    fn golden_reveal_and_game_over() {
+        // The wall of mines keeps the reveal on the right from reaching the left column
+        let mut minefield = from_text("\
+width=5
+height=3
+mines=3
+.*...
+.*...
+.*...
+").unwrap();
+        minefield.reveal_coord(&Coord2D(3, 1));
+        assert_eq!(minefield.game_over(), MinesweeperStatus::InProgress);
+        assert_eq!(to_text(&minefield, true), "\
+width=5
+height=3
+mines=3
+.*ooo
+.*ooo
+.*ooo
+");
+
+        for y in 0..3 {
+            assert_eq!(minefield.reveal_coord(&Coord2D(0, y)).len(), 1);
+        }
+        assert_eq!(minefield.game_over(), MinesweeperStatus::GameWon);
+
+        minefield.reveal_coord(&Coord2D(1, 1));
+        assert_eq!(minefield.game_over(), MinesweeperStatus::MineTriggered);
+    }
+
+    #[test]
+    fn header_has_to_match_the_board() {
+        assert!(from_text("width=4\n*..o.\n").is_err());
+        assert!(from_text("mines=2\n*...\n").is_err());
+        assert!(from_text("topology=cubic\n*...\n").is_err());
+        assert!(from_text("*...\n*..\n").is_err());
+    }
+
+    #[test]
+    fn mbf_round_trip() {
+        let minefield = from_text(BOARD).unwrap();
+        let bytes = to_mbf(&minefield).unwrap();
+        assert_eq!(bytes, vec![5, 3, 0, 3, 0, 0, 1, 1, 4, 2]);
+
+        let loaded = from_mbf(&bytes).unwrap();
+        assert_eq!(mines(&loaded), mines(&minefield));
+        assert_eq!(to_mbf(&loaded).unwrap(), bytes);
+        assert!(from_mbf(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn mbf_only_fits_square_boards() {
+        let minefield = from_text("topology=toroidal\n*...\n....\n").unwrap();
+        assert!(to_mbf(&minefield).is_err());
+    }
+}
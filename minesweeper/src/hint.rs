@@ -0,0 +1,101 @@
+// This file contains the hints for new players. Pressing the hint key highlights a move that
+// follows from the revealed numbers, or shows the chance of a mine on every covered tile when
+// there is no such move.
+
+use bevy::prelude::{
+    Commands,
+    Component,
+    Resource,
+    Res,
+    ResMut,
+    Query,
+    With,
+    Entity,
+    Input,
+    KeyCode,
+    Color,
+    DespawnRecursiveExt,
+    DetectChanges,
+};
+
+use crate::board::MinesweeperBoard;
+use crate::minefield::Minefield;
+use crate::solver::{Hint, Solver};
+
+// Not H, which moves the cursor
+pub const HINT_KEY: KeyCode = KeyCode::I;
+const HINT_SAFE_COLOR: Color = Color::rgba(0.1, 0.9, 0.1, 0.6);
+const HINT_MINE_COLOR: Color = Color::rgba(0.9, 0.1, 0.1, 0.6);
+const HEAT_MAP_ALPHA: f32 = 0.5;
+
+// Drawn above the covers and their markers
+const OVERLAY_Z: i32 = 10;
+
+// The number of hints used in the current game
+#[derive(Resource, Default)]
+pub struct HintsUsed(pub u32);
+
+// Tag component for the highlighted hint and the tiles of the heat map
+#[derive(Component)]
+pub struct HintOverlay;
+
+pub fn reset_hints(mut hints: ResMut<HintsUsed>) {
+    hints.0 = 0;
+}
+
+// Shows a hint on the hint key, or hides the one that is shown
+pub fn show_hint(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    minefield: Res<Minefield>,
+    board: Query<&MinesweeperBoard>,
+    overlays: Query<Entity, With<HintOverlay>>,
+    mut hints: ResMut<HintsUsed>,
+) {
+    // The solver needs the whole board, which an endless one never has
+    if !keys.just_pressed(HINT_KEY) || !minefield.mines_placed() || minefield.is_endless() {
+        return;
+    }
+    if !overlays.is_empty() {
+        for entity in overlays.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+
+    let board = board.single();
+    hints.0 += 1;
+
+    let mut solver = Solver::new(&minefield);
+    match solver.hint(minefield.flagged()) {
+        Some(Hint::Safe(coord)) => {
+            commands.spawn(board.tile_sprite_at_coord(coord.0, coord.1, OVERLAY_Z, HINT_SAFE_COLOR))
+                .insert(HintOverlay);
+        },
+        Some(Hint::Mine(coord)) => {
+            commands.spawn(board.tile_sprite_at_coord(coord.0, coord.1, OVERLAY_Z, HINT_MINE_COLOR))
+                .insert(HintOverlay);
+        },
+        // No certain move, fall back to a heat map from green for safe to red for a mine
+        None => {
+            for (coord, probability) in solver.mine_probabilities() {
+                let color = Color::rgba(probability, 1.0 - probability, 0.0, HEAT_MAP_ALPHA);
+                commands.spawn(board.tile_sprite_at_coord(coord.0, coord.1, OVERLAY_Z, color))
+                    .insert(HintOverlay);
+            }
+        },
+    }
+}
+
+// A hint is only valid for the board it was given on, so it is hidden when the board changes
+pub fn clear_hint_overlay(
+    mut commands: Commands,
+    minefield: Res<Minefield>,
+    overlays: Query<Entity, With<HintOverlay>>,
+) {
+    if minefield.is_changed() {
+        for entity in overlays.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,229 @@
+// This file contains the heads-up display shown above the minesweeper board: the number of mines
+// left to flag, or the score of an endless run, a reset face and the game clock. Games with more
+// than one life show the lives left next to them.
+
+use bevy::prelude::{
+    Commands,
+    Component,
+    Resource,
+    Res,
+    ResMut,
+    Query,
+    With,
+    Without,
+    Changed,
+    NextState,
+    AssetServer,
+    Color,
+    BuildChildren,
+    Children,
+    NodeBundle,
+    TextBundle,
+    ButtonBundle,
+    default,
+};
+use bevy::text::{Text, TextStyle};
+use bevy::time::{Time, Stopwatch};
+use bevy::ui::{Style, Size, Val, UiRect, JustifyContent, AlignItems, Interaction};
+
+use arcade_util::ArcadeState;
+
+use crate::minefield::Minefield;
+use crate::util::MinesweeperStatus;
+
+const HUD_COLOR: Color = Color::rgb(0.2, 0.2, 0.2);
+const COUNTER_COLOR: Color = Color::rgb(0.9, 0.1, 0.1);
+const FACE_COLOR: Color = Color::rgb(0.4, 0.4, 0.4);
+const FACE_TEXT_COLOR: Color = Color::rgb(1.0, 0.85, 0.0);
+const PRACTICE_COLOR: Color = Color::rgb(0.3, 0.8, 1.0);
+
+// The time spent on the current game, which starts on the first reveal and stops when the game is
+// decided
+#[derive(Resource, Default)]
+pub struct MinesweeperClock(pub Stopwatch);
+
+// Tag component for the root of the HUD
+#[derive(Component)]
+pub struct MinesweeperHud;
+
+// Tag component for the text showing the number of mines minus the number of flags
+#[derive(Component)]
+pub struct MineCounterText;
+
+// Tag component for the text showing the clock
+#[derive(Component)]
+pub struct ClockText;
+
+// Tag component for the label shown above the counters in practice mode
+#[derive(Component)]
+pub struct PracticeLabel;
+
+// Tag component for the text showing the lives left
+#[derive(Component)]
+pub struct LivesText;
+
+// Tag component for the face button which restarts the board
+#[derive(Component)]
+pub struct ResetFace;
+
+pub fn spawn_hud(
+    mut commands: Commands,
+    minefield: Res<Minefield>,
+    asset_server: Res<AssetServer>,
+) {
+    let digit_font = asset_server.load("fonts/pixeled.ttf");
+    let face_font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let counter_style = TextStyle {
+        font: digit_font,
+        font_size: 24.0,
+        color: COUNTER_COLOR,
+    };
+    let counter_node_style = Style {
+        size: Size::new(Val::Px(120.0), Val::Auto),
+        margin: UiRect::horizontal(Val::Px(20.0)),
+        justify_content: JustifyContent::Center,
+        ..default()
+    };
+
+    commands.spawn(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Px(80.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        ..default()
+    })
+    .insert(MinesweeperHud)
+    .with_children(|parent| {
+        if minefield.is_practice() {
+            parent.spawn(TextBundle::from_section(
+                "PRACTICE",
+                TextStyle {
+                    font: face_font.clone(),
+                    font_size: 24.0,
+                    color: PRACTICE_COLOR,
+                })
+                .with_style(Style {
+                    margin: UiRect::horizontal(Val::Px(20.0)),
+                    ..default()
+                }))
+                .insert(PracticeLabel);
+        }
+        parent.spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(5.0)),
+                ..default()
+            },
+            background_color: HUD_COLOR.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("", counter_style.clone())
+                .with_style(counter_node_style.clone()))
+                .insert(MineCounterText);
+
+            parent.spawn(ButtonBundle {
+                style: Style {
+                    size: Size::new(Val::Px(60.0), Val::Px(60.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: FACE_COLOR.into(),
+                ..default()
+            })
+            .insert(ResetFace)
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: face_font,
+                        font_size: 36.0,
+                        color: FACE_TEXT_COLOR,
+                    }));
+            });
+
+            parent.spawn(TextBundle::from_section("", counter_style.clone())
+                .with_style(counter_node_style))
+                .insert(ClockText);
+        });
+        if minefield.lives().is_some_and(|lives| lives > 1) {
+            parent.spawn(TextBundle::from_section("", counter_style)
+                .with_style(Style {
+                    margin: UiRect::horizontal(Val::Px(20.0)),
+                    ..default()
+                }))
+                .insert(LivesText);
+        }
+    });
+}
+
+pub fn reset_clock(mut clock: ResMut<MinesweeperClock>) {
+    clock.0.reset();
+}
+
+// Only runs the clock between the first reveal and the end of the game
+pub fn tick_clock(
+    time: Res<Time>,
+    minefield: Res<Minefield>,
+    mut clock: ResMut<MinesweeperClock>,
+) {
+    if minefield.mines_placed() && minefield.game_over() == MinesweeperStatus::InProgress {
+        clock.0.tick(time.delta());
+    }
+}
+
+pub fn update_hud(
+    minefield: Res<Minefield>,
+    clock: Res<MinesweeperClock>,
+    mut counter_text: Query<&mut Text, (With<MineCounterText>, Without<ClockText>)>,
+    mut clock_text: Query<&mut Text, (With<ClockText>, Without<MineCounterText>)>,
+    mut face_text: Query<&mut Text, (Without<ClockText>, Without<MineCounterText>)>,
+    face: Query<&Children, With<ResetFace>>,
+) {
+    // An endless board has no number of mines, so it counts the score instead
+    for mut text in &mut counter_text {
+        text.sections[0].value = if minefield.is_endless() {
+            format!("{:03}", minefield.num_revealed_safe())
+        } else {
+            format!("{:03}", minefield.num_mines() as i32 - minefield.num_flagged() as i32)
+        };
+    }
+    for mut text in &mut clock_text {
+        text.sections[0].value = format!("{:03}", clock.0.elapsed().as_secs().min(999));
+    }
+    for children in &face {
+        for child in children {
+            if let Ok(mut text) = face_text.get_mut(*child) {
+                text.sections[0].value = match minefield.game_over() {
+                    // A practice game goes on after a mine, until the move is undone
+                    MinesweeperStatus::InProgress if minefield.triggered_mine().is_some() => ":(",
+                    MinesweeperStatus::InProgress => ":)",
+                    MinesweeperStatus::MineTriggered => ":(",
+                    MinesweeperStatus::GameWon => "B)",
+                }.to_string();
+            }
+        }
+    }
+}
+
+pub fn update_lives_text(minefield: Res<Minefield>, mut texts: Query<&mut Text, With<LivesText>>) {
+    let Some(lives_left) = minefield.lives_left() else { return };
+    for mut text in &mut texts {
+        text.sections[0].value = format!("Lives {}", lives_left);
+    }
+}
+
+// Restarts the board by re-entering the minesweeper state
+pub fn reset_face_system(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<ResetFace>)>,
+    mut next_state: ResMut<NextState<ArcadeState>>,
+) {
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Clicked {
+            next_state.set(ArcadeState::PlayingMinesweeper);
+        }
+    }
+}
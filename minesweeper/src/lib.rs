@@ -1,7 +1,24 @@
 mod util;
 mod board;
+mod tile_view;
+mod explosion;
 mod minefield;
+mod solver;
+mod format;
 mod systems;
+mod hud;
+mod hint;
+mod cursor;
+mod stats;
+mod practice;
+mod replay;
+mod versus;
 mod plugin;
 
 pub use plugin::MinesweeperPlugin;
+// The minefield and its solver don't need bevy to run, so they can be used headlessly
+pub use minefield::{Minefield, HistoryEntry};
+pub use solver::{Solver, Constraint, Hint, is_solvable_from};
+pub use util::{Tile, Cover, MinesweeperStatus};
+pub use format::{to_text, from_text, to_mbf, from_mbf};
+pub use replay::{ReplayEvent, ReplayEventKind, replay_to_text, replay_from_text};
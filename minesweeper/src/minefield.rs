@@ -1,125 +1,656 @@
 use std::collections::{HashMap, HashSet};
 
-use bevy::prelude::{
-    Resource,
-    Query,
-    Res,
-    AssetServer,
-    Commands,
-    BuildChildren, ResMut
-};
-use rand::seq::IteratorRandom;
+use bevy::prelude::{Resource, Res, ResMut};
+use rand::{Rng, SeedableRng, rngs::SmallRng, seq::IteratorRandom};
 
-use arcade_util::{Coord2D, CoordConfiguration};
+use arcade_util::{ArcadeError, Coord2D, CoordConfiguration, MinesweeperSettings, SafeStart, Topology};
 
 use crate::util::{
-    MineNeighbor,
-    Mine,
-    TILE_COLOR,
-    BOARD_SIZE,
-    NUM_MINES,
+    CHUNK_SIZE,
+    ENDLESS_MIN_DENSITY,
+    ENDLESS_START_RADIUS,
+    NO_GUESS_MAX_ATTEMPTS,
+    NO_GUESS_WORK_BUDGET,
     Cover, Tile, MinesweeperStatus
 };
-use crate::board::MinesweeperBoard;
+use crate::solver::Solver;
 
 // This file should contain the Minefield struct and its associated impl block. Preferably also the
 // components, resources, and systems that operate on it.
 
 // Add revealed and flagged components
-#[derive(Resource)]
+#[derive(Resource, Clone)]
 pub struct Minefield {
     width: i32,
     height: i32,
+    num_mines: usize,
+    safe_start: SafeStart,
+    // Decides the neighbors of the tiles, and so the numbers, the openings and the safe start
+    topology: Topology,
+    question_marks: bool,
+    no_guess: bool,
+    seed: u64,
+    rng: SmallRng,
+    mines_placed: bool,
     tiles: HashMap<Coord2D<i32>, Tile>,
+    // Kept up to date while placing mines and revealing, so the game can be decided without
+    // going through all tiles
+    num_safe_tiles: usize,
+    num_revealed_safe: usize,
+    // The least number of clicks needed to clear the board, known once the mines are placed
+    three_bv: usize,
     revealed: HashSet<Coord2D<i32>>,
     flagged: HashSet<Coord2D<i32>>,
+    // Kept apart from the flags, a question mark says nothing about whether there is a mine
+    questioned: HashSet<Coord2D<i32>>,
+    triggered: Option<Coord2D<i32>>,
+    // In practice mode a triggered mine doesn't end the game, and every move can be undone
+    practice: bool,
+    history: Vec<HistoryEntry>,
+    // Endless minefields have no bounds and place their mines chunk by chunk
+    endless: Option<Endless>,
+    // In versus mode a mine that is stepped on gets a flag instead of ending the game, and is
+    // counted to give the player a time penalty
+    versus: bool,
+    mines_hit: usize,
+    // The number of mines that can be stepped on before the game is lost, one in a classic game.
+    // Mines stepped on before that stay revealed. There are none in versus mode, where a mine
+    // costs time instead
+    lives: Option<usize>,
+    // A board loaded from a file belongs to no difficulty, so its games are not recorded
+    imported: bool,
+    // The tiles which may look different since the tile views last followed the minefield, or
+    // none when all of them may, as for a new minefield or a lost game
+    changed: Option<HashSet<Coord2D<i32>>>,
+}
+
+// The mines of an endless minefield are placed in square chunks of tiles when they are first
+// needed. Every chunk gets its own random generator from the seed and its coordinates, so that a
+// chunk is the same whenever, and in whichever order, it is placed
+#[derive(Clone)]
+struct Endless {
+    density: f64,
+    mined_chunks: HashSet<Coord2D<i32>>,
+}
+
+// A move in practice mode, with what is needed to roll it back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistoryEntry {
+    // The tiles uncovered by a reveal or a chord, and the question marks that were on them
+    Reveal {
+        revealed: HashSet<Coord2D<i32>>,
+        questioned: Vec<Coord2D<i32>>,
+    },
+    // A tile of which the cover was cycled, and the cover it had before
+    Cover(Coord2D<i32>, Cover),
 }
 
 // Implementation of minefield
 impl Minefield {
 
-    pub fn build(width: i32, height: i32, num_mines: i32) -> Self {
-        let mut rng = rand::thread_rng();
+    // Build an empty minefield. The mines are placed on the first reveal, so that the first
+    // revealed tile (and its neighbors, depending on safe_start) never contains a mine. The mines
+    // are placed randomly from the given seed
+    pub fn build(width: i32, height: i32, num_mines: i32, safe_start: SafeStart, seed: u64) -> Self {
         let tiles = HashMap::from_iter(
             (0..width).flat_map(|x| (0..height)
                     .map(move |y| (Coord2D(x, y), Tile::Empty)))
         );
 
-        let mut minefield = Minefield {
+        Minefield {
             width,
             height,
+            num_mines: num_mines.max(0) as usize,
+            safe_start,
+            topology: Topology::Square,
+            question_marks: true,
+            no_guess: false,
+            seed,
+            rng: SmallRng::seed_from_u64(seed),
+            mines_placed: false,
+            num_safe_tiles: tiles.len(),
+            num_revealed_safe: 0,
+            three_bv: 0,
             tiles,
             revealed: HashSet::with_capacity((width * height) as usize),
             flagged: HashSet::new(),
+            questioned: HashSet::new(),
+            triggered: None,
+            practice: false,
+            history: Vec::new(),
+            endless: None,
+            versus: false,
+            mines_hit: 0,
+            lives: Some(1),
+            imported: false,
+            changed: None,
+        }
+    }
+
+    // Build an endless minefield with the given share of mines. The area around the origin has no
+    // mines and is revealed right away, which gives the run its start. Without edges to join, a
+    // torus plays like the square topology
+    pub fn endless(density: f64, topology: Topology, seed: u64) -> Self {
+        let mut minefield = Minefield {
+            topology,
+            endless: Some(Endless {
+                density: density.clamp(ENDLESS_MIN_DENSITY, 1.0),
+                mined_chunks: HashSet::new(),
+            }),
+            mines_placed: true,
+            tiles: HashMap::new(),
+            num_safe_tiles: 0,
+            ..Minefield::build(1, 1, 0, SafeStart::Opening, seed)
         };
+        minefield.reveal_tiles(&Coord2D(0, 0), &mut Vec::new());
+        minefield
+    }
 
-        for coord in (0..width).flat_map(|x| (0..height).map(move |y| Coord2D(x, y)))
-            .choose_multiple(&mut rng, num_mines as usize)
-        {
-            minefield.set_bomb(&coord);
+    // Build the two minefields of a versus game on the chosen difficulty. The mines are placed
+    // once, by revealing the middle of the board, and the minefield is then copied. Both players
+    // start from the same opening on the same board
+    pub fn versus(settings: &MinesweeperSettings) -> [Minefield; 2] {
+        let mut minefield = Minefield {
+            versus: true,
+            lives: None,
+            ..Minefield::from_settings(&MinesweeperSettings {
+                practice: false,
+                endless: false,
+                ..settings.clone()
+            })
+        };
+        minefield.reveal_coord(&Coord2D(minefield.width / 2, minefield.height / 2));
+        [minefield.clone(), minefield]
+    }
+
+    // Build an empty minefield for the chosen difficulty
+    pub fn from_settings(settings: &MinesweeperSettings) -> Self {
+        let (width, height, num_mines) = settings.dimensions();
+        let seed = settings.seed.unwrap_or_else(rand::random);
+        let minefield = if settings.endless {
+            Minefield::endless(settings.density, settings.topology, seed)
+        } else {
+            Minefield::build(width, height, num_mines, settings.safe_start, seed)
+        };
+        Minefield {
+            topology: settings.topology,
+            question_marks: settings.question_marks,
+            no_guess: settings.no_guess,
+            practice: settings.practice,
+            lives: Some(settings.lives()),
+            ..minefield
+        }
+    }
+
+    // Build a minefield with mines at the given coordinates, as if they had already been placed
+    pub fn from_mines(
+        width: i32,
+        height: i32,
+        topology: Topology,
+        mines: &[Coord2D<i32>],
+    ) -> Result<Self, ArcadeError> {
+        if width < 1 || height < 1 {
+            return Err(ArcadeError::new(format!("A {}x{} board has no tiles", width, height)));
         }
 
-        minefield
+        let mut minefield = Minefield {
+            topology,
+            ..Minefield::build(width, height, mines.len() as i32, SafeStart::Cell, 0)
+        };
+        for coord in mines.iter() {
+            if !minefield.tiles.contains_key(coord) {
+                return Err(ArcadeError::new(format!(
+                    "The mine at ({}, {}) is outside of the {}x{} board",
+                    coord.0, coord.1, width, height)));
+            }
+            if minefield.is_mine(coord) {
+                return Err(ArcadeError::new(format!(
+                    "There are two mines at ({}, {})", coord.0, coord.1)));
+            }
+            minefield.set_bomb(coord);
+        }
+        minefield.mines_placed = true;
+        minefield.three_bv = minefield.compute_three_bv();
+        Ok(minefield)
+    }
+
+    // The same minefield with the given number of lives, before any mine is stepped on
+    pub fn with_lives(self, lives: usize) -> Self {
+        Minefield { lives: Some(lives.max(1)), ..self }
+    }
+
+    // The same minefield, marked as loaded from a file
+    pub fn imported(self) -> Self {
+        Minefield { imported: true, ..self }
+    }
+
+    // Restore the state of a tile without revealing any of its neighbors, used when loading a game
+    pub(crate) fn restore_tile(&mut self, coord: &Coord2D<i32>, revealed: bool, cover: Cover) {
+        if revealed {
+            self.mark_changed(coord);
+            if self.revealed.insert(*coord) {
+                if self.is_mine(coord) {
+                    self.hit_mine(coord);
+                } else {
+                    self.num_revealed_safe += 1;
+                }
+            }
+            return;
+        }
+        match cover {
+            Cover::Flagged => { self.flagged.insert(*coord); },
+            Cover::Questioned => { self.questioned.insert(*coord); },
+            Cover::Unflagged => (),
+        }
+        self.mark_changed(coord);
+    }
+
+    // Remember that the tile may look different, for the next time the tile views are synced
+    fn mark_changed(&mut self, coord: &Coord2D<i32>) {
+        if let Some(changed) = &mut self.changed {
+            changed.insert(*coord);
+        }
+    }
+
+    // Takes the tiles which may look different since this was last called, or none if all of them
+    // may. The tile views use this to only update what changed
+    pub fn take_changed(&mut self) -> Option<HashSet<Coord2D<i32>>> {
+        self.changed.replace(HashSet::new())
+    }
+
+    pub fn is_practice(&self) -> bool {
+        self.practice
+    }
+
+    pub fn is_endless(&self) -> bool {
+        self.endless.is_some()
+    }
+
+    pub fn is_imported(&self) -> bool {
+        self.imported
+    }
+
+    // The number of mines stepped on in versus mode, or with lives to spare
+    pub fn mines_hit(&self) -> usize {
+        self.mines_hit
+    }
+
+    pub fn lives(&self) -> Option<usize> {
+        self.lives
+    }
+
+    // The number of mines that can still be stepped on before the game is lost
+    pub fn lives_left(&self) -> Option<usize> {
+        self.lives.map(|lives| lives.saturating_sub(self.mines_hit))
+    }
+
+    // The score of an endless run, which is the number of tiles revealed without hitting a mine
+    pub fn num_revealed_safe(&self) -> usize {
+        self.num_revealed_safe
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn is_revealed(&self, coord: &Coord2D<i32>) -> bool {
+        self.revealed.contains(coord)
+    }
+
+    // The cover on a tile which has not been revealed
+    pub fn cover(&self, coord: &Coord2D<i32>) -> Cover {
+        if self.flagged.contains(coord) {
+            Cover::Flagged
+        } else if self.questioned.contains(coord) {
+            Cover::Questioned
+        } else {
+            Cover::Unflagged
+        }
+    }
+
+    pub fn three_bv(&self) -> usize {
+        self.three_bv
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn num_tiles(&self) -> usize {
+        self.tiles.len()
+    }
+
+    // The tile at the given coordinate. Tiles of an endless minefield are only known once the mines
+    // around them have been placed, which happens when they are revealed
+    pub fn tile(&self, coord: &Coord2D<i32>) -> Option<Tile> {
+        match self.tiles.get(coord) {
+            None if self.is_endless() => Some(Tile::Empty),
+            tile => tile.copied(),
+        }
+    }
+
+    pub fn revealed(&self) -> &HashSet<Coord2D<i32>> {
+        &self.revealed
+    }
+
+    pub fn flagged(&self) -> &HashSet<Coord2D<i32>> {
+        &self.flagged
+    }
+
+    pub fn coords(&self) -> impl Iterator<Item = Coord2D<i32>> + '_ {
+        self.tiles.keys().copied()
+    }
+
+    pub fn mines_placed(&self) -> bool {
+        self.mines_placed
+    }
+
+    pub fn num_mines(&self) -> usize {
+        self.num_mines
+    }
+
+    pub fn num_flagged(&self) -> usize {
+        self.flagged.len()
+    }
+
+    pub fn is_mine(&self, coord: &Coord2D<i32>) -> bool {
+        self.tiles.get(coord) == Some(&Tile::Mine)
+    }
+
+    // The mine which was revealed first, i.e., the one that ended the game
+    pub fn triggered_mine(&self) -> Option<Coord2D<i32>> {
+        self.triggered
+    }
+
+    // Flag all mines that are not flagged yet, returns the newly flagged coordinates
+    pub fn flag_remaining_mines(&mut self) -> Vec<Coord2D<i32>> {
+        let unflagged: Vec<Coord2D<i32>> = self.tiles.iter()
+            .filter(|(coord, tile)| **tile == Tile::Mine && !self.flagged.contains(coord))
+            .map(|(coord, _)| *coord)
+            .collect();
+
+        for coord in unflagged.iter() {
+            self.questioned.remove(coord);
+            self.flagged.insert(*coord);
+            self.mark_changed(coord);
+        }
+        unflagged
+    }
+
+    // Place the mines anywhere but in the safe area around the first revealed coordinate. In no
+    // guess mode the mines are re-rolled until the solver can clear the board from the first
+    // reveal. That is given up on after a bounded number of attempts or amount of solver work, in
+    // which case the last layout is kept. Either way the layout only depends on the seed and the
+    // first reveal
+    fn place_mines(&mut self, first: &Coord2D<i32>) {
+        let mut safe = HashSet::from([*first]);
+        if self.safe_start == SafeStart::Opening {
+            safe.extend(self.neighbors(first));
+        }
+
+        let mut candidates: Vec<Coord2D<i32>> = self.tiles.keys()
+            .filter(|coord| !safe.contains(coord))
+            .copied()
+            .collect();
+        // Too crowded for a safe opening, fall back to only keeping the first tile safe
+        if candidates.len() < self.num_mines {
+            candidates.extend(safe.iter().filter(|coord| *coord != first));
+        }
+        // The tiles are stored unordered, sort them so that the seed decides the layout
+        candidates.sort_by_key(|coord| (coord.1, coord.0));
+
+        let mut work_left = NO_GUESS_WORK_BUDGET;
+        for attempt in 1.. {
+            self.clear_mines();
+            let mines = candidates.iter().copied().choose_multiple(&mut self.rng, self.num_mines);
+            for coord in mines.iter() {
+                self.set_bomb(coord);
+            }
+            if !self.no_guess {
+                break;
+            }
+
+            let mut solver = Solver::from_first_click(self, first);
+            if solver.solve_within(work_left) {
+                break;
+            }
+            work_left = work_left.saturating_sub(solver.work());
+            if attempt >= NO_GUESS_MAX_ATTEMPTS || work_left == 0 {
+                bevy::log::warn!(
+                    "No board without guessing found in {} attempts, it may need a guess",
+                    attempt);
+                break;
+            }
+        }
+        self.mines_placed = true;
+        self.three_bv = self.compute_three_bv();
+    }
+
+    // The 3BV of the board: one click for every opening, i.e., an area of connected empty tiles
+    // together with the numbers around it, and one for every number which is not next to one
+    fn compute_three_bv(&self) -> usize {
+        let mut opened = HashSet::new();
+        let mut three_bv = 0;
+
+        for (coord, tile) in self.tiles.iter() {
+            if *tile != Tile::Empty || opened.contains(coord) {
+                continue;
+            }
+            three_bv += 1;
+            let mut stack = vec![*coord];
+            while let Some(coord) = stack.pop() {
+                if opened.insert(coord) && self.tiles[&coord] == Tile::Empty {
+                    stack.extend(self.neighbors(&coord));
+                }
+            }
+        }
+
+        three_bv + self.tiles.iter()
+            .filter(|(coord, tile)| matches!(tile, Tile::Number(_)) && !opened.contains(coord))
+            .count()
+    }
+
+    fn clear_mines(&mut self) {
+        self.tiles.values_mut().for_each(|tile| *tile = Tile::Empty);
+        self.num_safe_tiles = self.tiles.len();
     }
 
     // set a bomb at the given coordinate and update the numbers around it
+    // An endless minefield only stores the tiles next to its mines, the others are empty
     fn set_bomb(&mut self, coord: &Coord2D<i32>) {
-        self.tiles.insert(*coord, Tile::Mine);
-        self.neighbors(&coord)
-            .iter()
-            .for_each(|neighbor| {
-                match self.tiles.get_mut(neighbor) {
-                    Some(Tile::Number(n)) => { *n += 1; },
-                    Some(Tile::Empty) => { self.tiles.insert(*neighbor, Tile::Number(1)); },
-                    _ => (),
-                };
-            });
+        if self.tiles.insert(*coord, Tile::Mine) != Some(Tile::Mine) && !self.is_endless() {
+            self.num_safe_tiles -= 1;
+        }
+        for neighbor in self.neighbors(coord) {
+            match self.tiles.get_mut(&neighbor) {
+                Some(Tile::Number(n)) => { *n += 1; },
+                Some(Tile::Empty) | None => { self.tiles.insert(neighbor, Tile::Number(1)); },
+                Some(Tile::Mine) => (),
+            };
+        }
     }
 
     // Return a coordinate iterator for the neighbors to a given coordinate
-    fn neighbors(&self, coord: &Coord2D<i32>) -> Vec<Coord2D<i32>> {
-        let Coord2D(x, y) = coord;
-        (x-1..=x+1)
-            .flat_map(move |x| (y-1..=y+1)
-                      .map(move |y| Coord2D(x, y)))
-            .filter(move |coord| *coord != Coord2D(*x, *y) && self.is_inside(*coord))
-            .collect()
+    pub(crate) fn neighbors(&self, coord: &Coord2D<i32>) -> impl Iterator<Item = Coord2D<i32>> {
+        self.topology.neighbors(self.bounds(), *coord)
+    }
+
+    // The width and height of a bounded minefield
+    fn bounds(&self) -> Option<(i32, i32)> {
+        self.endless.is_none().then_some((self.width, self.height))
     }
 
-    // Return whether the given coordinate is inside the minefield or not
-    fn is_inside(&self, coord: Coord2D<i32>) -> bool {
-        coord.0 >= 0 && coord.0 < self.width && coord.1 >= 0 && coord.1 < self.height
+    // Place the mines of the chunks around the given coordinate of an endless minefield, after
+    // which the tile is known. Chunks are square in any topology
+    fn place_endless_mines(&mut self, coord: &Coord2D<i32>) {
+        let chunk = Coord2D(coord.0.div_euclid(CHUNK_SIZE), coord.1.div_euclid(CHUNK_SIZE));
+        for neighbor in std::iter::once(chunk).chain(Topology::Square.neighbors(None, chunk)) {
+            self.place_chunk_mines(neighbor);
+        }
+    }
+
+    fn place_chunk_mines(&mut self, chunk: Coord2D<i32>) {
+        let Some(endless) = self.endless.as_mut() else { return };
+        if !endless.mined_chunks.insert(chunk) {
+            return;
+        }
+        let density = endless.density;
+
+        // Mix the chunk coordinates into the seed, such that neighboring chunks differ
+        let chunk_seed = self.seed
+            ^ (chunk.0 as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+            ^ (chunk.1 as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f);
+        let mut rng = SmallRng::seed_from_u64(chunk_seed);
+
+        for y in chunk.1 * CHUNK_SIZE..(chunk.1 + 1) * CHUNK_SIZE {
+            for x in chunk.0 * CHUNK_SIZE..(chunk.0 + 1) * CHUNK_SIZE {
+                let is_start = x.abs() <= ENDLESS_START_RADIUS && y.abs() <= ENDLESS_START_RADIUS;
+                if rng.gen_bool(density) && !is_start {
+                    self.set_bomb(&Coord2D(x, y));
+                    self.num_mines += 1;
+                }
+            }
+        }
     }
 
     // Reveal a tile at the given coordinate, add it to revealed, and reveal neighbors if it is
-    // possible
+    // possible. Empty areas are flooded with a stack rather than recursion, since they can be
+    // large enough to overflow the call stack on big boards
     // Returns a set of coordinates that were revealed
     pub fn reveal_coord(&mut self, coord: &Coord2D<i32>) -> HashSet<Coord2D<i32>> {
+        let mut questioned = Vec::new();
+        let revealed = self.reveal_tiles(coord, &mut questioned);
+        self.remember_reveal(&revealed, questioned);
+        revealed
+    }
+
+    // Reveal from the given coordinate as described above, keeping the question marks that get
+    // removed so that the reveal can be undone
+    fn reveal_tiles(
+        &mut self,
+        coord: &Coord2D<i32>,
+        questioned: &mut Vec<Coord2D<i32>>,
+    ) -> HashSet<Coord2D<i32>> {
         if self.revealed.contains(coord) {
             return HashSet::new();
         }
+        assert!(
+            self.is_endless() || self.tiles.contains_key(coord),
+            "Tried to reveal a tile that doesn't exist");
 
-        let old_revealed = self.revealed.clone();
+        if !self.mines_placed {
+            self.place_mines(coord);
+        }
 
-        self.revealed.insert(*coord);
+        // Only a tile that is played directly can be a mine, the flood stops at the numbers
+        if self.versus && self.tiles.get(coord) == Some(&Tile::Mine) {
+            self.questioned.remove(coord);
+            self.flagged.insert(*coord);
+            self.mark_changed(coord);
+            self.mines_hit += 1;
+            return HashSet::new();
+        }
 
-        match self.tiles.get(coord) {
-            Some(Tile::Empty) => {
-                self.neighbors(coord)
-                    .iter()
-                    .for_each(|neighbor| { self.reveal_coord(neighbor); });
-            },
-            Some(_) => (),
-            None => panic!("Tried to reveal a tile that doesn't exist"),
+        let mut newly_revealed = HashSet::new();
+        let mut stack = vec![*coord];
+        while let Some(coord) = stack.pop() {
+            if !self.revealed.insert(coord) {
+                continue;
+            }
+            if self.questioned.remove(&coord) {
+                questioned.push(coord);
+            }
+            newly_revealed.insert(coord);
+            self.mark_changed(&coord);
+
+            if self.is_endless() {
+                self.place_endless_mines(&coord);
+            }
+            match self.tiles.get(&coord).copied().unwrap_or(Tile::Empty) {
+                Tile::Mine => self.hit_mine(&coord),
+                Tile::Empty => {
+                    self.num_revealed_safe += 1;
+                    // A flag stops the flood, even a wrong one, and stays on its covered tile
+                    stack.extend(self.neighbors(&coord).filter(|neighbor| {
+                        !self.revealed.contains(neighbor) && !self.flagged.contains(neighbor)
+                    }));
+                },
+                Tile::Number(_) => {
+                    self.num_revealed_safe += 1;
+                },
+            }
         }
+        newly_revealed
+    }
 
-        &self.revealed - &old_revealed
+    // Count a mine that was stepped on and revealed. The one that takes the last life is the
+    // triggered mine which loses the game, which shows all mines and wrong flags
+    fn hit_mine(&mut self, coord: &Coord2D<i32>) {
+        self.mines_hit += 1;
+        if self.lives_left() == Some(0) {
+            self.triggered.get_or_insert(*coord);
+            self.changed = None;
+        }
     }
 
-    // Toggle the flag at the given coordinate, return whether the flag is now set or not
-    pub fn toggle_flag(&mut self, coord: &Coord2D<i32>) -> Cover {
-        if self.flagged.contains(coord) {
-            self.flagged.remove(coord);
+    // Chord on the given coordinate: if it is a revealed number with exactly as many flags around it,
+    // reveal all of its other neighbors. A wrong flag means a mine gets revealed.
+    // Returns a set of coordinates that were revealed
+    pub fn chord(&mut self, coord: &Coord2D<i32>) -> HashSet<Coord2D<i32>> {
+        let Some(Tile::Number(n)) = self.tiles.get(coord) else { return HashSet::new() };
+        if !self.revealed.contains(coord) {
+            return HashSet::new();
+        }
+
+        let neighbors: Vec<_> = self.neighbors(coord).collect();
+        let num_flags = neighbors.iter()
+            .filter(|c| self.flagged.contains(c) && !self.revealed.contains(c))
+            .count();
+        if num_flags != *n as usize {
+            return HashSet::new();
+        }
+
+        let mut revealed = HashSet::new();
+        let mut questioned = Vec::new();
+        for neighbor in neighbors.iter() {
+            if !self.flagged.contains(neighbor) {
+                revealed.extend(self.reveal_tiles(neighbor, &mut questioned));
+            }
+        }
+        self.remember_reveal(&revealed, questioned);
+        revealed
+    }
+
+    // Only practice games keep their history, since it costs a copy of every reveal
+    fn remember_reveal(&mut self, revealed: &HashSet<Coord2D<i32>>, questioned: Vec<Coord2D<i32>>) {
+        if self.practice && !revealed.is_empty() {
+            self.history.push(HistoryEntry::Reveal { revealed: revealed.clone(), questioned });
+        }
+    }
+
+    // Cycle the cover at the given coordinate from unflagged to flagged to questioned (if question
+    // marks are enabled) and back to unflagged. Returns the new cover
+    pub fn cycle_cover(&mut self, coord: &Coord2D<i32>) -> Cover {
+        if self.practice {
+            self.history.push(HistoryEntry::Cover(*coord, self.cover(coord)));
+        }
+        self.mark_changed(coord);
+        if self.flagged.remove(coord) {
+            if self.question_marks {
+                self.questioned.insert(*coord);
+                Cover::Questioned
+            } else {
+                Cover::Unflagged
+            }
+        } else if self.questioned.remove(coord) {
             Cover::Unflagged
         } else {
             self.flagged.insert(*coord);
@@ -127,17 +658,49 @@ impl Minefield {
         }
     }
 
+    // Roll back the last move of a practice game. The mines stay where they were placed. Returns
+    // the move that was undone, so that the board can follow
+    pub fn undo(&mut self) -> Option<HistoryEntry> {
+        let entry = self.history.pop()?;
+        match &entry {
+            HistoryEntry::Reveal { revealed, questioned } => {
+                for coord in revealed {
+                    self.revealed.remove(coord);
+                    self.mark_changed(coord);
+                    if !self.is_mine(coord) {
+                        self.num_revealed_safe -= 1;
+                        continue;
+                    }
+                    self.mines_hit -= 1;
+                    if self.triggered == Some(*coord) {
+                        self.triggered = None;
+                    }
+                }
+                self.questioned.extend(questioned);
+            },
+            HistoryEntry::Cover(coord, cover) => {
+                self.flagged.remove(coord);
+                self.questioned.remove(coord);
+                self.restore_tile(coord, false, *cover);
+            },
+        }
+        Some(entry)
+    }
+
     // A function which returns whether the game is won or not, alongside the outcome of the game
-    // as a MinesweeperStatus enum
+    // as a MinesweeperStatus enum. Running out of lives doesn't end a practice game, the mine that
+    // took the last one can be undone
     pub fn game_over(&self) -> MinesweeperStatus {
-        // Return MinesweeperStatus::MineTriggered if a mine is in the set of revealed tiles
-        if self.revealed.iter().any(|coord| self.tiles.get(coord) == Some(&Tile::Mine)) {
+        // Return MinesweeperStatus::MineTriggered once the mines stepped on took all lives
+        if self.lives_left() == Some(0) && !self.practice {
             MinesweeperStatus::MineTriggered
-        // Return Game won if the number of revealed tiles and flagged tiles is equal to the total
-        // board size minus the number of mines
-        } else if self.revealed.len()
-            == (self.width * self.height) as usize
-                - self.tiles.values().filter(|&tile| *tile == Tile::Mine).count() {
+        // An endless run goes on until its one mine is hit
+        } else if self.is_endless() {
+            MinesweeperStatus::InProgress
+        // Return Game won if all tiles without a mine have been revealed
+        } else if self.triggered.is_none()
+            && self.mines_placed
+            && self.num_revealed_safe == self.num_safe_tiles {
             MinesweeperStatus::GameWon
         } else {
             MinesweeperStatus::InProgress
@@ -148,7 +711,7 @@ impl Minefield {
 
 impl Default for Minefield {
     fn default() -> Self {
-        Minefield::build(BOARD_SIZE, BOARD_SIZE, NUM_MINES)
+        Minefield::from_settings(&MinesweeperSettings::default())
     }
 }
 
@@ -158,70 +721,177 @@ impl<'a> CoordConfiguration<'a, i32> for Minefield {
     }
 }
 
-// Reset the minefield, clearing all revealed and flagged tiles and setting new mines
+// A board that was loaded from a file, to be played in the next game
+#[derive(Resource, Default)]
+pub struct ImportedBoard(pub Option<Minefield>);
+
+// Reset the minefield to an empty one for the chosen difficulty. New mines are placed on the next
+// first reveal. An imported board is played as it was loaded instead
 pub fn reset_minefield(
     mut minefield: ResMut<Minefield>,
+    mut imported: ResMut<ImportedBoard>,
+    settings: Res<MinesweeperSettings>,
 ) {
-    let num_mines = minefield.tiles.values().filter(|&tile| *tile == Tile::Mine).count();
-
-    let tiles: HashMap<Coord2D<i32>, Tile> = HashMap::from_iter(
-        (0..minefield.width).flat_map(|x| (0..minefield.height)
-                .map(move |y| (Coord2D(x, y), Tile::Empty)))
-    );
-
-    *minefield = Minefield {
-        tiles,
-        revealed: HashSet::with_capacity((minefield.width * minefield.height) as usize),
-        flagged: HashSet::new(),
-        ..*minefield
+    *minefield = match imported.0.take() {
+        Some(board) => board,
+        None => Minefield::from_settings(&settings),
     };
+}
 
-    let mut rng = rand::thread_rng();
-    for coord in (0..minefield.width).flat_map(|x| (0..minefield.height).map(move |y| Coord2D(x, y)))
-        .choose_multiple(&mut rng, num_mines as usize)
-    {
-        minefield.set_bomb(&coord);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arcade_util::Difficulty;
+    use crate::solver::is_solvable_from;
+
+    fn no_guess_mines(difficulty: Difficulty, seed: u64, first: &Coord2D<i32>) -> Minefield {
+        let mut minefield = Minefield::from_settings(&MinesweeperSettings {
+            difficulty,
+            no_guess: true,
+            seed: Some(seed),
+            ..MinesweeperSettings::default()
+        });
+        minefield.reveal_coord(first);
+        minefield
     }
-}
 
-// spawn the minefield components
-pub fn spawn_minefield(
-    mut commands: Commands,
-    minefield: Res<Minefield>,
-    board: Query<&MinesweeperBoard>,
-    asset_server: Res<AssetServer>,
-) {
-    // If we currently have a board Component in the world, spawn the minefield
-    if let Ok(board) = board.get_single() {
-
-        let font = asset_server.load("fonts/pixeled.ttf");
-        let img  = asset_server.load("sprites/mine.png");
-
-        // Spawn the tiles, i.e., empty, number, or mine
-        for coord in minefield.configuration() {
-            let mut cmd = commands.spawn_empty();
-
-            let tile_id = cmd.insert(
-                board.tile_sprite_at_coord(coord.0, coord.1, 1, TILE_COLOR)
-            )
-            .insert(*coord)
-            .id();
-
-            match minefield.tiles.get(coord) {
-                Some(Tile::Number(n)) => {
-                    commands.entity(tile_id).insert(MineNeighbor(*n))
-                        .with_children(|builder| {
-                            builder.spawn(MineNeighbor(*n).to_sprite(font.clone()));
-                        });
-                },
-                Some(Tile::Mine) => {
-                    commands.entity(tile_id).insert(Mine)
-                        .with_children(|builder| {
-                            builder.spawn(Mine.to_sprite(img.clone()));
-                        });
-                },
-                _ => (),
+    fn mines(minefield: &Minefield) -> HashSet<Coord2D<i32>> {
+        minefield.coords().filter(|coord| minefield.is_mine(coord)).collect()
+    }
+
+    // The outcome of the game from going through all tiles, as it was decided before the counts
+    // were kept up to date
+    fn recounted_game_over(minefield: &Minefield) -> MinesweeperStatus {
+        let safe: Vec<_> = minefield.coords().filter(|coord| !minefield.is_mine(coord)).collect();
+        if minefield.triggered_mine().is_some() {
+            MinesweeperStatus::MineTriggered
+        } else if safe.iter().all(|coord| minefield.is_revealed(coord)) {
+            MinesweeperStatus::GameWon
+        } else {
+            MinesweeperStatus::InProgress
+        }
+    }
+
+    #[test]
+    fn large_reveal_matches_recount() {
+        // A recursive flood fill would need a frame per tile, far more than this stack holds
+        let test = std::thread::Builder::new().stack_size(256 * 1024).spawn(|| {
+            let size = 250;
+            let mut empty = Minefield::build(size, size, 0, SafeStart::Opening, 0);
+            let revealed = empty.reveal_coord(&Coord2D(size / 2, size / 2));
+            assert_eq!(revealed.len(), (size * size) as usize);
+            assert_eq!(empty.game_over(), MinesweeperStatus::GameWon);
+            assert_eq!(recounted_game_over(&empty), MinesweeperStatus::GameWon);
+
+            let mut sparse = Minefield::build(size, size, size * size / 200, SafeStart::Opening, 0);
+            sparse.reveal_coord(&Coord2D(size / 2, size / 2));
+            assert_eq!(sparse.game_over(), recounted_game_over(&sparse));
+
+            // Reveal the rest of the safe tiles, the game is only won with the last one
+            let mut covered: Vec<_> = sparse.coords()
+                .filter(|coord| !sparse.is_mine(coord) && !sparse.is_revealed(coord))
+                .collect();
+            covered.sort_by_key(|coord| (coord.1, coord.0));
+            for coord in covered.iter() {
+                assert_eq!(sparse.game_over(), MinesweeperStatus::InProgress);
+                sparse.reveal_coord(coord);
             }
+            assert_eq!(sparse.game_over(), MinesweeperStatus::GameWon);
+            assert_eq!(recounted_game_over(&sparse), MinesweeperStatus::GameWon);
+
+            let mine = sparse.coords().find(|coord| sparse.is_mine(coord)).unwrap();
+            sparse.reveal_coord(&mine);
+            assert_eq!(sparse.game_over(), MinesweeperStatus::MineTriggered);
+            assert_eq!(recounted_game_over(&sparse), MinesweeperStatus::MineTriggered);
+        }).unwrap();
+        test.join().unwrap();
+    }
+
+    #[test]
+    fn no_guess_layout_only_depends_on_the_seed() {
+        let first = Coord2D(8, 8);
+        for seed in 0..5 {
+            let minefield = no_guess_mines(Difficulty::Intermediate, seed, &first);
+            assert_eq!(mines(&minefield), mines(&no_guess_mines(Difficulty::Intermediate, seed, &first)));
+            assert_eq!(minefield.num_mines(), mines(&minefield).len());
+            assert!(is_solvable_from(&minefield, &first));
         }
+        assert_ne!(
+            mines(&no_guess_mines(Difficulty::Intermediate, 0, &first)),
+            mines(&no_guess_mines(Difficulty::Intermediate, 1, &first)));
+    }
+
+    #[test]
+    fn changed_tiles_follow_the_moves() {
+        // A column of mines with an empty area on either side
+        let mines: Vec<_> = (0..5).map(|y| Coord2D(2, y)).collect();
+        let mut minefield = Minefield::from_mines(5, 5, Topology::Square, &mines).unwrap();
+        assert_eq!(minefield.take_changed(), None);
+        assert_eq!(minefield.take_changed(), Some(HashSet::new()));
+
+        let revealed = minefield.reveal_coord(&Coord2D(0, 0));
+        assert_eq!(minefield.take_changed(), Some(revealed));
+
+        minefield.cycle_cover(&Coord2D(2, 0));
+        assert_eq!(minefield.take_changed(), Some(HashSet::from([Coord2D(2, 0)])));
+
+        // Losing shows every mine and wrong flag
+        minefield.reveal_coord(&Coord2D(2, 1));
+        assert_eq!(minefield.take_changed(), None);
+    }
+
+    #[test]
+    fn chord_next_to_a_wrong_flag_in_an_opening() {
+        // A mine in the corner, with a wrong flag on a safe tile of the opening next to it
+        let mut minefield = Minefield::from_mines(5, 5, Topology::Square, &[Coord2D(0, 0)]).unwrap();
+        minefield.cycle_cover(&Coord2D(2, 2));
+        minefield.reveal_coord(&Coord2D(4, 4));
+        assert_eq!(minefield.cover(&Coord2D(2, 2)), Cover::Flagged);
+        assert!(!minefield.is_revealed(&Coord2D(2, 2)));
+
+        // The wrong flag is still a flag on a covered tile, so a chord on the 1 next to it trusts
+        // it and reveals the mine
+        minefield.chord(&Coord2D(1, 1));
+        assert_eq!(minefield.game_over(), MinesweeperStatus::MineTriggered);
+
+        // A flag on a tile that is revealed anyway doesn't count towards a chord
+        let mut minefield = Minefield::from_mines(5, 5, Topology::Square, &[Coord2D(0, 0)]).unwrap();
+        minefield.cycle_cover(&Coord2D(2, 2));
+        minefield.restore_tile(&Coord2D(2, 2), true, Cover::Flagged);
+        minefield.reveal_coord(&Coord2D(1, 1));
+        assert!(minefield.chord(&Coord2D(1, 1)).is_empty());
+        assert_eq!(minefield.game_over(), MinesweeperStatus::InProgress);
+    }
+
+    #[test]
+    fn flood_leaves_the_flag_count_alone() {
+        let mut minefield = Minefield::from_mines(6, 6, Topology::Square, &[Coord2D(0, 0)]).unwrap();
+        minefield.cycle_cover(&Coord2D(3, 3));
+        minefield.cycle_cover(&Coord2D(0, 0));
+        assert_eq!(minefield.num_flagged(), 2);
+
+        // The flood goes around both flags, so the mine counter of the HUD still takes off both
+        minefield.reveal_coord(&Coord2D(5, 5));
+        assert_eq!(minefield.num_flagged(), 2);
+        assert!(!minefield.is_revealed(&Coord2D(3, 3)));
+        assert!(minefield.is_revealed(&Coord2D(4, 4)));
+
+        // Taking the wrong flag off and revealing its tile finishes the board
+        minefield.cycle_cover(&Coord2D(3, 3));
+        minefield.cycle_cover(&Coord2D(3, 3));
+        minefield.reveal_coord(&Coord2D(3, 3));
+        assert_eq!(minefield.num_flagged(), 1);
+        assert_eq!(minefield.game_over(), MinesweeperStatus::GameWon);
+    }
+
+    #[test]
+    fn endless_runs_end_at_the_first_mine() {
+        let minefield = Minefield::from_settings(&MinesweeperSettings {
+            endless: true,
+            lives: 3,
+            seed: Some(0),
+            ..MinesweeperSettings::default()
+        });
+        assert_eq!(minefield.lives(), Some(1));
     }
 }
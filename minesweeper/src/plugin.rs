@@ -7,30 +7,116 @@ use bevy::prelude::{
     OnEnter,
     OnExit,
     OnUpdate,
+    IntoSystemConfig,
     IntoSystemConfigs,
     apply_system_buffers,
+    in_state,
     IntoSystemAppConfigs
 };
 
 use arcade_util::{
     ArcadeState,
+    pan_camera,
+    zoom_camera,
+    reset_camera,
+    not_panning,
+    MinesweeperSettings,
+    MinesweeperReplay,
     despawn_component,
-    Coord2D
 };
 
 use crate::{
     board::{
         spawn_board,
+        fit_camera_to_board,
         MinesweeperBoard,
     },
     systems::{
         minesweeper_game_over,
-        reveal_coord,
+        mouse_actions,
+        apply_actions,
+        reveal_board,
+        release_mouse_press,
+        show_result_popup,
+        reset_phase,
+        export_board,
+        import_board,
+    },
+    util::{
+        MinesweeperAction,
+        HoveredCell,
+        MousePress,
+        MinesweeperPhase,
+        MinesweeperResult,
+        RevealTimer,
+    },
+    hud::{
+        MinesweeperClock,
+        MinesweeperHud,
+        spawn_hud,
+        reset_clock,
+        tick_clock,
+        update_hud,
+        update_lives_text,
+        reset_face_system,
+    },
+    cursor::{
+        MinesweeperCursor,
+        spawn_cursor,
+        keyboard_actions,
+        sync_cursor_highlight,
+        follow_cursor,
+    },
+    replay::{
+        MinesweeperRecording,
+        ReplayPlayer,
+        ReplayHud,
+        reset_recording,
+        record_events,
+        save_recording,
+        load_replay,
+        spawn_replay_hud,
+        replay_controls,
+        rebuild_replay,
+        play_replay,
+        update_replay_hud,
+    },
+    practice::undo_action,
+    explosion::animate_explosions,
+    versus::{
+        VersusMatch,
+        VersusHud,
+        start_versus,
+        place_versus_boards,
+        versus_keyboard_actions,
+        versus_mouse_actions,
+        sync_versus_views,
+        tick_versus_clock,
+        update_versus_hud,
+        versus_game_over,
+        leave_versus,
+    },
+    stats::{
+        ClickCounts,
+        reset_clicks,
+    },
+    hint::{
+        HintsUsed,
+        HintOverlay,
+        reset_hints,
+        show_hint,
+        clear_hint_overlay,
     },
     minefield::{
         Minefield,
-        spawn_minefield,
+        ImportedBoard,
         reset_minefield,
+    },
+    tile_view::{
+        TileImages,
+        sync_tile_views,
+        spawn_endless_chunks,
+        cull_tile_chunks,
     }
 };
 
@@ -39,27 +125,162 @@ pub struct MinesweeperPlugin;
 impl Plugin for MinesweeperPlugin {
     fn build(&self, app: &mut App) {
         app
+            .init_resource::<MinesweeperSettings>()
+            .init_resource::<MinesweeperReplay>()
             .insert_resource(Minefield::default())
+            .init_resource::<MinesweeperClock>()
+            .init_resource::<MinesweeperResult>()
+            .init_resource::<RevealTimer>()
+            .init_resource::<HintsUsed>()
+            .init_resource::<ClickCounts>()
+            .init_resource::<MinesweeperCursor>()
+            .init_resource::<HoveredCell>()
+            .init_resource::<MousePress>()
+            .init_resource::<TileImages>()
+            .init_resource::<MinesweeperRecording>()
+            .init_resource::<ReplayPlayer>()
+            .init_resource::<VersusMatch>()
+            .init_resource::<ImportedBoard>()
+            .add_event::<MinesweeperAction>()
+            .add_state::<MinesweeperPhase>()
             .add_systems(
                 (
+                    reset_minefield,
+                    reset_clock,
+                    reset_hints,
+                    reset_clicks,
+                    reset_recording,
                     spawn_board,
+                    spawn_hud,
                     apply_system_buffers,
-                    spawn_minefield,
+                    spawn_cursor,
+                    fit_camera_to_board,
                 ).chain()
                 .in_schedule(OnEnter(ArcadeState::PlayingMinesweeper))
             )
-            .add_systems(
+            .add_systems(  // Things to run whilst the game is being played
                 (
-                    reveal_coord,
+                    mouse_actions.run_if(not_panning),
+                    keyboard_actions,
+                    sync_cursor_highlight,
+                    follow_cursor,
+                    record_events,
+                    apply_actions,
+                    undo_action,
+                    clear_hint_overlay,
+                    show_hint,
                     minesweeper_game_over,
                 ).chain()
+                .distributive_run_if(in_state(MinesweeperPhase::Playing))
+                .in_set(OnUpdate(ArcadeState::PlayingMinesweeper)))
+            // The tile views follow the moves of this frame, and the flags put down on a win
+            .add_system(
+                sync_tile_views
+                    .after(minesweeper_game_over)
+                    .in_set(OnUpdate(ArcadeState::PlayingMinesweeper)))
+            .add_systems(  // The camera can be moved around a board larger than the window
+                (
+                    pan_camera,
+                    zoom_camera,
+                    spawn_endless_chunks,
+                    cull_tile_chunks,
+                ).chain()
+                .in_set(OnUpdate(ArcadeState::PlayingMinesweeper)))
+            .add_systems(  // Things to run in every phase of the game
+                (
+                    tick_clock,
+                    update_hud,
+                    update_lives_text,
+                    show_result_popup.run_if(in_state(MinesweeperPhase::Revealing)),
+                    reset_face_system,
+                    export_board,
+                    import_board,
+                    animate_explosions,
+                ).chain()
                 .in_set(OnUpdate(ArcadeState::PlayingMinesweeper)))
             .add_systems(
                 (
-                    despawn_component::<Coord2D<i32>>,
+                    despawn_component::<HintOverlay>,
+                    reveal_board,
+                    release_mouse_press,
+                    save_recording,
+                ).in_schedule(OnEnter(MinesweeperPhase::Revealing)))
+            .add_systems(
+                (
                     despawn_component::<MinesweeperBoard>,
-                    reset_minefield,
+                    despawn_component::<MinesweeperHud>,
+                    despawn_component::<HintOverlay>,
+                    reset_camera,
+                    reset_phase,
+                ).chain()
+                .in_schedule(OnExit(ArcadeState::PlayingMinesweeper)))
+            .add_systems(
+                (
+                    load_replay,
+                    spawn_board,
+                    spawn_replay_hud,
+                    apply_system_buffers,
+                    spawn_cursor,
+                    fit_camera_to_board,
+                ).chain()
+                .in_schedule(OnEnter(ArcadeState::ReplayingMinesweeper)))
+            .add_systems(  // Things to run whilst a replay is watched
+                (
+                    replay_controls.run_if(not_panning),
+                    rebuild_replay,
+                    play_replay,
+                    apply_actions,
+                    sync_tile_views,
+                    sync_cursor_highlight,
+                    follow_cursor,
+                    update_replay_hud,
+                    animate_explosions,
+                ).chain()
+                .in_set(OnUpdate(ArcadeState::ReplayingMinesweeper)))
+            .add_systems(
+                (
+                    pan_camera,
+                    zoom_camera,
+                    cull_tile_chunks,
+                ).chain()
+                .in_set(OnUpdate(ArcadeState::ReplayingMinesweeper)))
+            .add_systems(
+                (
+                    despawn_component::<MinesweeperBoard>,
+                    despawn_component::<ReplayHud>,
+                    reset_camera,
+                ).in_schedule(OnExit(ArcadeState::ReplayingMinesweeper)))
+            .add_systems(
+                (
+                    start_versus,
+                    apply_system_buffers,
+                    place_versus_boards,
                 ).chain()
-                .in_schedule(OnExit(ArcadeState::PlayingMinesweeper)));
+                .in_schedule(OnEnter(ArcadeState::PlayingMinesweeperVersus)))
+            .add_systems(  // Things to run whilst two players race
+                (
+                    versus_keyboard_actions.run_if(not_panning),
+                    versus_mouse_actions.run_if(not_panning),
+                    sync_cursor_highlight,
+                    tick_versus_clock,
+                    versus_game_over,
+                    sync_versus_views,
+                    update_versus_hud,
+                    leave_versus,
+                ).chain()
+                .in_set(OnUpdate(ArcadeState::PlayingMinesweeperVersus)))
+            .add_systems(
+                (
+                    pan_camera,
+                    zoom_camera,
+                    cull_tile_chunks,
+                ).chain()
+                .in_set(OnUpdate(ArcadeState::PlayingMinesweeperVersus)))
+            .add_systems(
+                (
+                    despawn_component::<MinesweeperBoard>,
+                    despawn_component::<VersusHud>,
+                    reset_camera,
+                ).in_schedule(OnExit(ArcadeState::PlayingMinesweeperVersus)));
     }
 }
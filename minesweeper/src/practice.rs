@@ -0,0 +1,16 @@
+// This file contains practice mode, in which triggering a mine doesn't end the game. Every move is
+// kept by the minefield, and the last one can be undone to try again.
+
+use bevy::prelude::{Res, ResMut, Input, KeyCode};
+
+use crate::minefield::Minefield;
+
+pub const UNDO_KEY: KeyCode = KeyCode::U;
+
+// Undoes the last move. The tile views follow the minefield, so the covers come back by themselves
+pub fn undo_action(keys: Res<Input<KeyCode>>, mut minefield: ResMut<Minefield>) {
+    if !minefield.is_practice() || !keys.any_just_pressed([UNDO_KEY, KeyCode::Back]) {
+        return;
+    }
+    minefield.undo();
+}
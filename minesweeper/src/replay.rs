@@ -0,0 +1,379 @@
+// This file contains the recording of minesweeper games and the replay viewer. Every action, and
+// every tile the cursor moves to, is recorded with the time since the board was shown. At the end
+// of a game the recording is saved with the layout of the board: the board in the text format of
+// format.rs, a `---` line and then one line per event.
+//
+//     ---
+//     0.000 cursor 4 4
+//     0.512 reveal 4 4
+//     1.200 cover 3 2
+//     1.900 chord 3 3
+
+use bevy::prelude::{
+    Commands,
+    Component,
+    Resource,
+    Res,
+    ResMut,
+    Query,
+    With,
+    Input,
+    KeyCode,
+    NextState,
+    EventReader,
+    EventWriter,
+    AssetServer,
+    DetectChanges,
+    NodeBundle,
+    TextBundle,
+    Color,
+    default,
+    BuildChildren,
+};
+use bevy::text::{Text, TextStyle};
+use bevy::time::{Time, Stopwatch};
+use bevy::ui::{Style, Size, Val, UiRect, JustifyContent};
+
+use arcade_util::{
+    ArcadeError,
+    ArcadeState,
+    Coord2D,
+    MinesweeperReplay,
+    MinesweeperSettings,
+    save_path,
+};
+
+use crate::cursor::MinesweeperCursor;
+use crate::format::{from_text, to_text};
+use crate::minefield::Minefield;
+use crate::systems::apply_action;
+use crate::util::{HoveredCell, MinesweeperAction, MinesweeperResult};
+
+const EVENTS_SEPARATOR: &str = "---";
+
+// The playback speeds of the viewer, the default being the speed of the game
+const SPEEDS: [f32; 5] = [0.5, 1.0, 2.0, 4.0, 8.0];
+const DEFAULT_SPEED: usize = 1;
+
+// How far the viewer jumps back or forward when scrubbing
+const SEEK_SECONDS: f32 = 5.0;
+
+const REPLAY_TEXT_COLOR: Color = Color::WHITE;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReplayEventKind {
+    Action(MinesweeperAction),
+    Cursor(Coord2D<i32>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReplayEvent {
+    pub seconds: f32,
+    pub kind: ReplayEventKind,
+}
+
+impl ReplayEvent {
+    fn to_line(self) -> String {
+        let (name, coord) = match self.kind {
+            ReplayEventKind::Action(MinesweeperAction::Reveal(coord)) => ("reveal", coord),
+            ReplayEventKind::Action(MinesweeperAction::CycleCover(coord)) => ("cover", coord),
+            ReplayEventKind::Action(MinesweeperAction::Chord(coord)) => ("chord", coord),
+            ReplayEventKind::Cursor(coord) => ("cursor", coord),
+        };
+        format!("{:.3} {} {} {}\n", self.seconds, name, coord.0, coord.1)
+    }
+
+    fn from_line(line: &str) -> Result<Self, ArcadeError> {
+        let invalid = || ArcadeError::new(format!("Invalid replay event: {}", line));
+
+        let [seconds, name, x, y] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+            return Err(invalid());
+        };
+        let seconds: f32 = seconds.parse().map_err(|_| invalid())?;
+        let coord = Coord2D(x.parse().map_err(|_| invalid())?, y.parse().map_err(|_| invalid())?);
+        let kind = match name {
+            "reveal" => ReplayEventKind::Action(MinesweeperAction::Reveal(coord)),
+            "cover" => ReplayEventKind::Action(MinesweeperAction::CycleCover(coord)),
+            "chord" => ReplayEventKind::Action(MinesweeperAction::Chord(coord)),
+            "cursor" => ReplayEventKind::Cursor(coord),
+            _ => return Err(invalid()),
+        };
+        Ok(ReplayEvent { seconds, kind })
+    }
+}
+
+// Write a replay of the events played on the minefield
+pub fn replay_to_text(minefield: &Minefield, events: &[ReplayEvent]) -> String {
+    let mut text = to_text(minefield, false);
+    text.push_str(EVENTS_SEPARATOR);
+    text.push('\n');
+    for event in events {
+        text.push_str(&event.to_line());
+    }
+    text
+}
+
+// Read a replay, i.e., the board it was played on and the events in order of time
+pub fn replay_from_text(text: &str) -> Result<(Minefield, Vec<ReplayEvent>), ArcadeError> {
+    let separator = text.lines()
+        .position(|line| line.trim() == EVENTS_SEPARATOR)
+        .ok_or_else(|| ArcadeError::new("A replay needs a --- line between the board and the events"))?;
+
+    let board: Vec<&str> = text.lines().take(separator).collect();
+    let minefield = from_text(&board.join("\n"))?;
+    let mut events = text.lines()
+        .skip(separator + 1)
+        .filter(|line| !line.trim().is_empty())
+        .map(ReplayEvent::from_line)
+        .collect::<Result<Vec<_>, _>>()?;
+    events.sort_by(|a, b| a.seconds.total_cmp(&b.seconds));
+    Ok((minefield, events))
+}
+
+// The events of the game being played
+#[derive(Resource, Default)]
+pub struct MinesweeperRecording {
+    stopwatch: Stopwatch,
+    events: Vec<ReplayEvent>,
+}
+
+pub fn reset_recording(mut recording: ResMut<MinesweeperRecording>) {
+    *recording = MinesweeperRecording::default();
+}
+
+pub fn record_events(
+    time: Res<Time>,
+    hovered: Res<HoveredCell>,
+    mut recording: ResMut<MinesweeperRecording>,
+    mut actions: EventReader<MinesweeperAction>,
+) {
+    let seconds = recording.stopwatch.tick(time.delta()).elapsed_secs();
+
+    if let (true, Some(coord)) = (hovered.is_changed(), hovered.0) {
+        recording.events.push(ReplayEvent { seconds, kind: ReplayEventKind::Cursor(coord) });
+    }
+    for action in actions.iter() {
+        recording.events.push(ReplayEvent { seconds, kind: ReplayEventKind::Action(*action) });
+    }
+}
+
+// Saves the game that just ended as the last game, and as the best one of the board if it was.
+// Practice games are not saved, since the moves that were undone can't be played back
+pub fn save_recording(
+    minefield: Res<Minefield>,
+    recording: Res<MinesweeperRecording>,
+    result: Res<MinesweeperResult>,
+    settings: Res<MinesweeperSettings>,
+) {
+    // Endless boards have no text form to replay them from
+    if minefield.is_practice() || minefield.is_endless() {
+        return;
+    }
+    let text = replay_to_text(&minefield, &recording.events);
+
+    let mut replays = vec![MinesweeperReplay::LastGame];
+    if result.is_best_time {
+        replays.push(MinesweeperReplay::BestTime(settings.board_name()));
+    }
+    for replay in replays {
+        let saved = save_path("minesweeper", &replay.file_name())
+            .and_then(|path| Ok(std::fs::write(path, &text)?));
+        if let Err(e) = saved {
+            bevy::log::warn!("Could not save the minesweeper replay: {}", e);
+        }
+    }
+}
+
+// The state of the replay viewer
+#[derive(Resource, Default)]
+pub struct ReplayPlayer {
+    // The board in the text format, the minefield is rebuilt from it when seeking
+    board: String,
+    events: Vec<ReplayEvent>,
+    next_event: usize,
+    seconds: f32,
+    speed: usize,
+    paused: bool,
+    seek_to: Option<f32>,
+}
+
+impl ReplayPlayer {
+    fn duration(&self) -> f32 {
+        self.events.last().map_or(0.0, |event| event.seconds)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.next_event == self.events.len()
+    }
+}
+
+// Tag component for the text showing the state of the replay viewer
+#[derive(Component)]
+pub struct ReplayHud;
+
+pub fn load_replay(
+    commands: Commands,
+    selection: Res<MinesweeperReplay>,
+    mut player: ResMut<ReplayPlayer>,
+    mut minefield: ResMut<Minefield>,
+    mut next_state: ResMut<NextState<ArcadeState>>,
+) {
+    let loaded = save_path("minesweeper", &selection.file_name())
+        .and_then(|path| Ok(std::fs::read_to_string(path)?))
+        .and_then(|text| replay_from_text(&text));
+
+    match loaded {
+        Ok((loaded, events)) => {
+            *player = ReplayPlayer {
+                board: to_text(&loaded, false),
+                events,
+                speed: DEFAULT_SPEED,
+                ..default()
+            };
+            *minefield = loaded;
+        },
+        Err(e) => {
+            *minefield = Minefield::default();
+            next_state.set(ArcadeState::Popup);
+            arcade_popup::spawn_popup(
+                commands,
+                "No replay".to_string(),
+                format!("Could not load the replay: {}", e),
+            );
+        },
+    }
+}
+
+pub fn spawn_replay_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Px(80.0)),
+            justify_content: JustifyContent::Center,
+            padding: UiRect::all(Val::Px(10.0)),
+            ..default()
+        },
+        ..default()
+    })
+    .insert(ReplayHud)
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                font_size: 24.0,
+                color: REPLAY_TEXT_COLOR,
+            }));
+    });
+}
+
+// Space pauses, up and down change the speed, left and right scrub through the game and escape
+// goes back to the menu
+pub fn replay_controls(
+    keys: Res<Input<KeyCode>>,
+    mut player: ResMut<ReplayPlayer>,
+    mut next_state: ResMut<NextState<ArcadeState>>,
+) {
+    if keys.just_pressed(KeyCode::Space) {
+        if player.is_finished() {
+            player.seek_to = Some(0.0);
+            player.paused = false;
+        } else {
+            player.paused = !player.paused;
+        }
+    }
+    if keys.just_pressed(KeyCode::Up) {
+        player.speed = (player.speed + 1).min(SPEEDS.len() - 1);
+    }
+    if keys.just_pressed(KeyCode::Down) {
+        player.speed = player.speed.saturating_sub(1);
+    }
+    if keys.just_pressed(KeyCode::Left) {
+        player.seek_to = Some(player.seconds - SEEK_SECONDS);
+    }
+    if keys.just_pressed(KeyCode::Right) {
+        player.seek_to = Some(player.seconds + SEEK_SECONDS);
+    }
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(ArcadeState::Menu);
+    }
+}
+
+// Jumps to another point in the game: the events up to that point are played on a fresh minefield,
+// which the tile views follow
+pub fn rebuild_replay(
+    mut player: ResMut<ReplayPlayer>,
+    mut minefield: ResMut<Minefield>,
+    mut cursor: ResMut<MinesweeperCursor>,
+) {
+    let Some(target) = player.seek_to.take() else { return };
+    let Ok(rebuilt) = from_text(&player.board) else { return };
+
+    *minefield = rebuilt;
+    player.seconds = target.clamp(0.0, player.duration());
+    player.next_event = 0;
+    while let Some(event) = player.events.get(player.next_event).copied() {
+        if event.seconds > player.seconds {
+            break;
+        }
+        match event.kind {
+            ReplayEventKind::Action(action) => apply_action(&mut minefield, &action),
+            ReplayEventKind::Cursor(coord) => {
+                *cursor = MinesweeperCursor { coord, shown: true };
+            },
+        }
+        player.next_event += 1;
+    }
+}
+
+// Plays the events which are due, the actions go the same way as the actions of a player
+pub fn play_replay(
+    time: Res<Time>,
+    mut player: ResMut<ReplayPlayer>,
+    mut cursor: ResMut<MinesweeperCursor>,
+    mut actions: EventWriter<MinesweeperAction>,
+) {
+    if player.paused || player.is_finished() {
+        return;
+    }
+
+    player.seconds += time.delta_seconds() * SPEEDS[player.speed];
+    while let Some(event) = player.events.get(player.next_event).copied() {
+        if event.seconds > player.seconds {
+            break;
+        }
+        match event.kind {
+            ReplayEventKind::Action(action) => actions.send(action),
+            ReplayEventKind::Cursor(coord) => *cursor = MinesweeperCursor { coord, shown: true },
+        }
+        player.next_event += 1;
+    }
+    player.seconds = player.seconds.min(player.duration());
+}
+
+pub fn update_replay_hud(
+    player: Res<ReplayPlayer>,
+    hud: Query<&bevy::prelude::Children, With<ReplayHud>>,
+    mut texts: Query<&mut Text>,
+) {
+    let state = if player.is_finished() {
+        "finished"
+    } else if player.paused {
+        "paused"
+    } else {
+        "playing"
+    };
+
+    for children in hud.iter() {
+        for child in children {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                text.sections[0].value = format!(
+                    "Replay {} at {}x: {:.1} / {:.1} s\n\
+                     Space: pause, Up/Down: speed, Left/Right: scrub, Escape: menu",
+                    state,
+                    SPEEDS[player.speed],
+                    player.seconds,
+                    player.duration());
+            }
+        }
+    }
+}
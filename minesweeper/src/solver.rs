@@ -0,0 +1,475 @@
+// This file contains a minesweeper solver which only makes the moves that follow logically from the
+// revealed numbers. It is used to generate boards which can be solved without guessing.
+
+use std::collections::{HashMap, HashSet};
+
+use arcade_util::Coord2D;
+
+use crate::minefield::Minefield;
+use crate::util::Tile;
+
+// Neighborhoods of unknown tiles up to this size have all of their mine layouts counted when
+// computing probabilities, larger ones are estimated from the numbers around each tile
+const MAX_ENUMERATED_TILES: usize = 20;
+
+// A revealed number, described by its covered neighbors that are not known to be mines and the
+// number of mines that are left among them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    pub cells: HashSet<Coord2D<i32>>,
+    pub mines: usize,
+}
+
+// A move which follows from the revealed numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hint {
+    Safe(Coord2D<i32>),
+    Mine(Coord2D<i32>),
+}
+
+// Plays a minefield using logic alone. It keeps its own record of revealed tiles and known mines,
+// so it can be run on a minefield without changing it
+pub struct Solver<'a> {
+    minefield: &'a Minefield,
+    revealed: HashSet<Coord2D<i32>>,
+    mines: HashSet<Coord2D<i32>>,
+    // The work done so far, counted in tiles looked at, which bounds the solver the same way on
+    // every machine
+    work: usize,
+}
+
+impl<'a> Solver<'a> {
+
+    // Start from the tiles revealed so far. Flags are not used, since they might be wrong
+    pub fn new(minefield: &'a Minefield) -> Self {
+        // A mine is only revealed in practice mode or when playing with lives, it is a known mine
+        let (mines, revealed) = minefield.revealed()
+            .iter()
+            .partition(|coord| minefield.is_mine(coord));
+        Solver { minefield, revealed, mines, work: 0 }
+    }
+
+    // Start from a board where only the given coordinate has been revealed
+    pub fn from_first_click(minefield: &'a Minefield, first: &Coord2D<i32>) -> Self {
+        let mut solver = Solver {
+            minefield,
+            revealed: HashSet::new(),
+            mines: HashSet::new(),
+            work: 0,
+        };
+        solver.reveal(first);
+        solver
+    }
+
+    // Whether every tile without a mine has been revealed
+    pub fn is_solved(&self) -> bool {
+        self.revealed.len() + self.minefield.num_mines() == self.minefield.num_tiles()
+    }
+
+    // Apply the rules until nothing more can be deduced, returns whether the board was solved
+    pub fn solve(&mut self) -> bool {
+        self.solve_within(usize::MAX)
+    }
+
+    // Apply the rules until nothing more can be deduced or the work done reaches the budget, in
+    // which case the board counts as not solved. Returns whether the board was solved
+    pub fn solve_within(&mut self, budget: usize) -> bool {
+        while self.work < budget && self.step() {}
+        self.is_solved()
+    }
+
+    // The work done so far, in tiles looked at
+    pub fn work(&self) -> usize {
+        self.work
+    }
+
+    // Apply the rules once, revealing the safe tiles and remembering the mines that were found.
+    // Returns whether anything was deduced
+    pub fn step(&mut self) -> bool {
+        // Every step builds the constraints of the revealed tiles, and may go through all tiles
+        // for the mine count rule
+        self.work += self.revealed.len() + self.minefield.num_tiles();
+        let (safe, mines) = self.deduce();
+        if safe.is_empty() && mines.is_empty() {
+            return false;
+        }
+
+        self.mines.extend(mines);
+        for coord in safe.iter() {
+            self.reveal(coord);
+        }
+        true
+    }
+
+    // Find the covered tiles that are certainly safe and those that are certainly mines. The
+    // simple rules are tried first, the more expensive ones only when those find nothing
+    pub fn deduce(&self) -> (HashSet<Coord2D<i32>>, HashSet<Coord2D<i32>>) {
+        let constraints = self.constraints();
+        let mut safe = HashSet::new();
+        let mut mines = HashSet::new();
+
+        // Single tile rule: a number with all of its mines found makes the rest of its neighbors
+        // safe, and a number with as many covered neighbors as mines left makes all of them mines
+        for constraint in constraints.iter() {
+            if constraint.mines == 0 {
+                safe.extend(constraint.cells.iter().copied());
+            } else if constraint.mines == constraint.cells.len() {
+                mines.extend(constraint.cells.iter().copied());
+            }
+        }
+        if !safe.is_empty() || !mines.is_empty() {
+            return (safe, mines);
+        }
+
+        // Subset and pairwise rule: if B needs as many more mines than A as it has tiles outside
+        // of A, those tiles are all mines and all of A's mines lie in the overlap, so A's tiles
+        // outside of B are safe. The subset rule is the case where one lies within the other
+        for (a, b) in Self::overlapping_pairs(&constraints) {
+            let only_a: Vec<_> = a.cells.difference(&b.cells).copied().collect();
+            let only_b: Vec<_> = b.cells.difference(&a.cells).copied().collect();
+            if b.mines >= a.mines && b.mines - a.mines == only_b.len() {
+                safe.extend(only_a);
+                mines.extend(only_b);
+            }
+        }
+        if !safe.is_empty() || !mines.is_empty() {
+            return (safe, mines);
+        }
+
+        // Mine count rule: once all mines are found the rest is safe, and if there are as many
+        // unknown tiles as mines left, they are all mines
+        let unknown: Vec<_> = self.minefield.coords()
+            .filter(|coord| !self.revealed.contains(coord) && !self.mines.contains(coord))
+            .collect();
+        let mines_left = self.minefield.num_mines().saturating_sub(self.mines.len());
+        if mines_left == 0 {
+            safe.extend(unknown);
+        } else if mines_left == unknown.len() {
+            mines.extend(unknown);
+        }
+        (safe, mines)
+    }
+
+    // One constraint per revealed number that still has unknown neighbors
+    pub fn constraints(&self) -> Vec<Constraint> {
+        let mut constraints: Vec<Constraint> = Vec::new();
+        for coord in self.revealed.iter() {
+            let Some(Tile::Number(n)) = self.minefield.tile(coord) else { continue };
+
+            let mut cells = HashSet::new();
+            let mut found = 0;
+            for neighbor in self.minefield.neighbors(coord) {
+                if self.mines.contains(&neighbor) {
+                    found += 1;
+                } else if !self.revealed.contains(&neighbor) {
+                    cells.insert(neighbor);
+                }
+            }
+
+            if !cells.is_empty() {
+                constraints.push(Constraint { cells, mines: (n as usize).saturating_sub(found) });
+            }
+        }
+        constraints
+    }
+
+    // Find one certain move the player has not made yet: a safe tile to reveal, or else a mine
+    // that has not been flagged. Returns None when there is no such move
+    pub fn hint(&mut self, flagged: &HashSet<Coord2D<i32>>) -> Option<Hint> {
+        loop {
+            let (safe, mines) = self.deduce();
+            if let Some(coord) = safe.iter().min_by_key(|coord| (coord.1, coord.0)) {
+                return Some(Hint::Safe(*coord));
+            }
+            if let Some(coord) = mines.iter()
+                .filter(|coord| !flagged.contains(coord))
+                .min_by_key(|coord| (coord.1, coord.0)) {
+                return Some(Hint::Mine(*coord));
+            }
+            if mines.is_empty() {
+                return None;
+            }
+            // Only mines the player already flagged were found, continue from those
+            self.mines.extend(mines);
+        }
+    }
+
+    // The chance of a mine on every unknown tile. For tiles next to revealed numbers, that is the
+    // share of the mine layouts around those numbers which put a mine on the tile. The other
+    // tiles share the mines that are expected to be left
+    pub fn mine_probabilities(&self) -> HashMap<Coord2D<i32>, f32> {
+        let constraints = self.constraints();
+        let mut probabilities: HashMap<Coord2D<i32>, f32> = self.mines.iter()
+            .map(|coord| (*coord, 1.0))
+            .collect();
+
+        for component in Self::components(&constraints) {
+            let mut cells: Vec<Coord2D<i32>> = component.iter()
+                .flat_map(|constraint| constraint.cells.iter().copied())
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect();
+            cells.sort_by_key(|coord| (coord.1, coord.0));
+
+            if cells.len() <= MAX_ENUMERATED_TILES {
+                let mut counter = LayoutCounter::new(&cells, &component);
+                counter.count(0);
+                for (cell, mines) in cells.iter().zip(counter.mines_per_cell.iter()) {
+                    let probability = *mines as f32 / counter.layouts.max(1) as f32;
+                    probabilities.insert(*cell, probability);
+                }
+            } else {
+                for cell in cells.iter() {
+                    let around: Vec<f32> = component.iter()
+                        .filter(|constraint| constraint.cells.contains(cell))
+                        .map(|constraint| constraint.mines as f32 / constraint.cells.len() as f32)
+                        .collect();
+                    probabilities.insert(*cell, around.iter().sum::<f32>() / around.len() as f32);
+                }
+            }
+        }
+
+        let interior: Vec<Coord2D<i32>> = self.minefield.coords()
+            .filter(|coord| !self.revealed.contains(coord) && !probabilities.contains_key(coord))
+            .collect();
+        let expected_mines_left = self.minefield.num_mines() as f32
+            - probabilities.values().sum::<f32>();
+        let probability = (expected_mines_left / interior.len().max(1) as f32).clamp(0.0, 1.0);
+        probabilities.extend(interior.into_iter().map(|coord| (coord, probability)));
+        probabilities
+    }
+
+    // Group the constraints which are connected through shared tiles
+    fn components(constraints: &[Constraint]) -> Vec<Vec<&Constraint>> {
+        let mut by_cell: HashMap<Coord2D<i32>, Vec<usize>> = HashMap::new();
+        for (i, constraint) in constraints.iter().enumerate() {
+            for cell in constraint.cells.iter() {
+                by_cell.entry(*cell).or_default().push(i);
+            }
+        }
+
+        let mut seen = vec![false; constraints.len()];
+        let mut components = Vec::new();
+        for start in 0..constraints.len() {
+            if seen[start] {
+                continue;
+            }
+            seen[start] = true;
+            let mut stack = vec![start];
+            let mut component = Vec::new();
+            while let Some(i) = stack.pop() {
+                component.push(&constraints[i]);
+                for cell in constraints[i].cells.iter() {
+                    for &j in by_cell[cell].iter() {
+                        if !seen[j] {
+                            seen[j] = true;
+                            stack.push(j);
+                        }
+                    }
+                }
+            }
+            components.push(component);
+        }
+        components
+    }
+
+    // All ordered pairs of different constraints that share at least one tile
+    fn overlapping_pairs(constraints: &[Constraint]) -> Vec<(&Constraint, &Constraint)> {
+        let mut by_cell: HashMap<Coord2D<i32>, Vec<usize>> = HashMap::new();
+        for (i, constraint) in constraints.iter().enumerate() {
+            for cell in constraint.cells.iter() {
+                by_cell.entry(*cell).or_default().push(i);
+            }
+        }
+
+        let mut pairs = HashSet::new();
+        for indices in by_cell.values() {
+            for &i in indices {
+                for &j in indices {
+                    if i != j {
+                        pairs.insert((i, j));
+                    }
+                }
+            }
+        }
+        pairs.into_iter().map(|(i, j)| (&constraints[i], &constraints[j])).collect()
+    }
+
+    // Reveal a tile the way the minefield does, opening up the neighbors of empty tiles
+    fn reveal(&mut self, coord: &Coord2D<i32>) {
+        let mut stack = vec![*coord];
+        while let Some(coord) = stack.pop() {
+            if !self.revealed.insert(coord) {
+                continue;
+            }
+            if self.minefield.tile(&coord) == Some(Tile::Empty) {
+                stack.extend(self.minefield.neighbors(&coord));
+            }
+        }
+    }
+}
+
+// Whether the minefield can be solved from the given first click without ever having to guess
+pub fn is_solvable_from(minefield: &Minefield, first: &Coord2D<i32>) -> bool {
+    Solver::from_first_click(minefield, first).solve()
+}
+
+// Counts the mine layouts of a group of tiles which agree with the constraints on them, and how
+// many of those put a mine on each tile
+struct LayoutCounter {
+    // The constraints each tile is part of
+    cell_constraints: Vec<Vec<usize>>,
+    needed: Vec<usize>,
+    placed: Vec<usize>,
+    // The number of tiles of each constraint which have not been decided yet
+    open: Vec<usize>,
+    layout: Vec<bool>,
+    mines_per_cell: Vec<u64>,
+    layouts: u64,
+}
+
+impl LayoutCounter {
+    fn new(cells: &[Coord2D<i32>], constraints: &[&Constraint]) -> Self {
+        LayoutCounter {
+            cell_constraints: cells.iter()
+                .map(|cell| (0..constraints.len())
+                    .filter(|i| constraints[*i].cells.contains(cell))
+                    .collect())
+                .collect(),
+            needed: constraints.iter().map(|constraint| constraint.mines).collect(),
+            placed: vec![0; constraints.len()],
+            open: constraints.iter().map(|constraint| constraint.cells.len()).collect(),
+            layout: vec![false; cells.len()],
+            mines_per_cell: vec![0; cells.len()],
+            layouts: 0,
+        }
+    }
+
+    // Decide the tiles from the given index on, skipping choices that can't satisfy a constraint
+    fn count(&mut self, i: usize) {
+        if i == self.layout.len() {
+            self.layouts += 1;
+            for (mines, is_mine) in self.mines_per_cell.iter_mut().zip(self.layout.iter()) {
+                *mines += *is_mine as u64;
+            }
+            return;
+        }
+
+        for is_mine in [false, true] {
+            let possible = self.cell_constraints[i].iter().all(|&c| {
+                let placed = self.placed[c] + is_mine as usize;
+                // The tile itself is still counted as open, hence the strict comparison
+                placed <= self.needed[c] && placed + self.open[c] > self.needed[c]
+            });
+            if !possible {
+                continue;
+            }
+
+            for &c in self.cell_constraints[i].iter() {
+                self.placed[c] += is_mine as usize;
+                self.open[c] -= 1;
+            }
+            self.layout[i] = is_mine;
+            self.count(i + 1);
+            for &c in self.cell_constraints[i].iter() {
+                self.placed[c] -= is_mine as usize;
+                self.open[c] += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arcade_util::Topology;
+    use crate::util::Cover;
+
+    // A square minefield with the given mines and revealed tiles, the revealed mines are known to
+    // the solver
+    fn board(width: i32, height: i32, mines: &[(i32, i32)], revealed: &[(i32, i32)]) -> Minefield {
+        let mines: Vec<_> = mines.iter().map(|&(x, y)| Coord2D(x, y)).collect();
+        let mut minefield = Minefield::from_mines(width, height, Topology::Square, &mines)
+            .unwrap()
+            .with_lives(mines.len());
+        for &(x, y) in revealed {
+            minefield.restore_tile(&Coord2D(x, y), true, Cover::Unflagged);
+        }
+        minefield
+    }
+
+    fn coords(coords: &[(i32, i32)]) -> HashSet<Coord2D<i32>> {
+        coords.iter().map(|&(x, y)| Coord2D(x, y)).collect()
+    }
+
+    #[test]
+    fn single_tile_rule_finds_mines() {
+        // . 1 ?  with a mine on the last tile
+        let minefield = board(3, 1, &[(2, 0)], &[(0, 0), (1, 0)]);
+        assert_eq!(Solver::new(&minefield).deduce(), (coords(&[]), coords(&[(2, 0)])));
+    }
+
+    #[test]
+    fn single_tile_rule_finds_safe_tiles() {
+        // * 1 ? ?  with the first mine known, so the one can't touch another
+        let minefield = board(4, 1, &[(0, 0), (3, 0)], &[(0, 0), (1, 0)]);
+        assert_eq!(Solver::new(&minefield).deduce(), (coords(&[(2, 0)]), coords(&[])));
+    }
+
+    #[test]
+    fn subset_and_pairwise_rules() {
+        // 1 2 1 1
+        // ? ? ? ?  with mines on the first and third tile of the bottom row. No number can be
+        // solved on its own, but the ones next to each other can
+        let minefield = board(
+            4, 2,
+            &[(0, 1), (2, 1)],
+            &[(0, 0), (1, 0), (2, 0), (3, 0)]);
+        let solver = Solver::new(&minefield);
+        assert!(solver.constraints().iter().all(|c| c.mines != 0 && c.mines != c.cells.len()));
+        assert_eq!(solver.deduce(), (coords(&[(1, 1), (3, 1)]), coords(&[(0, 1), (2, 1)])));
+    }
+
+    #[test]
+    fn mine_count_rule() {
+        // All mines are known, so the untouched tiles are safe
+        let minefield = board(5, 1, &[(0, 0)], &[(0, 0)]);
+        assert_eq!(
+            Solver::new(&minefield).deduce(),
+            (coords(&[(1, 0), (2, 0), (3, 0), (4, 0)]), coords(&[])));
+
+        // As many unknown tiles as mines left, so they are all mines
+        let minefield = board(2, 1, &[(0, 0), (1, 0)], &[]);
+        assert_eq!(Solver::new(&minefield).deduce(), (coords(&[]), coords(&[(0, 0), (1, 0)])));
+    }
+
+    #[test]
+    fn solvable_from_first_click() {
+        let minefield = board(3, 1, &[(2, 0)], &[]);
+        assert!(is_solvable_from(&minefield, &Coord2D(0, 0)));
+    }
+
+    #[test]
+    fn solving_stops_at_the_work_budget() {
+        // The tile behind the mine is only known to be safe after a few steps
+        let minefield = board(1, 4, &[(0, 1)], &[]);
+        let mut solver = Solver::from_first_click(&minefield, &Coord2D(0, 3));
+        assert!(!solver.solve_within(0));
+        assert_eq!(solver.work(), 0);
+
+        let mut solver = Solver::from_first_click(&minefield, &Coord2D(0, 3));
+        assert!(solver.solve_within(100));
+        assert!(solver.work() > 0);
+    }
+
+    #[test]
+    fn fifty_fifty_is_not_solvable() {
+        // ? 1 .
+        // ? 1 .  with one mine on the left, which can be either of the two tiles
+        let minefield = board(3, 2, &[(0, 0)], &[]);
+        let mut solver = Solver::from_first_click(&minefield, &Coord2D(2, 1));
+        assert!(!solver.solve());
+        assert_eq!(solver.revealed, coords(&[(1, 0), (1, 1), (2, 0), (2, 1)]));
+        assert!(!is_solvable_from(&minefield, &Coord2D(2, 1)));
+    }
+}
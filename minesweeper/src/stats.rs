@@ -0,0 +1,64 @@
+// This file contains the statistics of a game of minesweeper: the clicks used, and the scores that
+// are kept per board once the game is decided.
+
+use bevy::prelude::{Resource, ResMut};
+
+use arcade_util::{MinesweeperStats, RunScores};
+
+use crate::util::MinesweeperAction;
+
+// The clicks used in the current game. Keyboard actions count as the click they stand in for
+#[derive(Resource, Default)]
+pub struct ClickCounts {
+    pub left: u32,
+    pub right: u32,
+    pub chord: u32,
+}
+
+impl ClickCounts {
+    pub fn count(&mut self, action: &MinesweeperAction) {
+        match action {
+            MinesweeperAction::Reveal(_) => self.left += 1,
+            MinesweeperAction::CycleCover(_) => self.right += 1,
+            MinesweeperAction::Chord(_) => self.chord += 1,
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.left + self.right + self.chord
+    }
+
+    // The scores of a won game on a board with the given 3BV
+    pub fn run_scores(&self, three_bv: usize, seconds: f32) -> RunScores {
+        RunScores {
+            seconds,
+            three_bv_per_second: three_bv as f32 / seconds.max(f32::EPSILON),
+            efficiency: 100.0 * three_bv as f32 / self.total().max(1) as f32,
+        }
+    }
+}
+
+impl std::fmt::Display for ClickCounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} left, {} right and {} chord clicks", self.left, self.right, self.chord)
+    }
+}
+
+pub fn reset_clicks(mut clicks: ResMut<ClickCounts>) {
+    *clicks = ClickCounts::default();
+}
+
+// Load the stats, update them and save them again. Failing to do so only costs the stats of this
+// game, so it is logged rather than shown
+pub fn update_stats<T: Default>(update: impl FnOnce(&mut MinesweeperStats) -> T) -> T {
+    let result = MinesweeperStats::load().and_then(|mut stats| {
+        let value = update(&mut stats);
+        stats.save()?;
+        Ok(value)
+    });
+
+    result.unwrap_or_else(|e| {
+        bevy::log::warn!("Could not update the minesweeper stats: {}", e);
+        T::default()
+    })
+}
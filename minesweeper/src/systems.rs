@@ -1,165 +1,408 @@
+use std::path::Path;
+
 use bevy::prelude::{
+    Camera,
+    GlobalTransform,
     Commands,
+    KeyCode,
     Query,
     ResMut,
-    Entity,
-    EventReader,
+    Input,
     Res,
-    AssetServer,
     NextState,
     MouseButton,
-    DespawnRecursiveExt,
-    BuildChildren
+    EventReader,
+    EventWriter,
 };
-use bevy::window::Window;
-use bevy::input::{ButtonState, mouse::MouseButtonInput};
+use bevy::ecs::system::SystemParam;
+use bevy::time::Time;
+use bevy::window::{Window, CursorMoved};
 
-use arcade_util::{Coord2D, ArcadeState};
+use arcade_util::{ArcadeState, Coord2D, MinesweeperSettings, save_path};
 
-use crate::minefield::Minefield;
-use crate::util::{Cover, MinesweeperStatus, TILE_COLOR_COVERED};
+use crate::minefield::{Minefield, ImportedBoard};
+use crate::format::{to_text, from_text, from_mbf};
+use crate::util::{
+    Cover,
+    MinesweeperStatus,
+    MinesweeperPhase,
+    MinesweeperAction,
+    HoveredCell,
+    MousePress,
+    MinesweeperResult,
+    RevealTimer,
+    EXPORT_KEY,
+    EXPORT_FILE,
+    IMPORT_KEY,
+    IMPORT_MBF_FILE,
+};
 use crate::board::MinesweeperBoard;
+use crate::hud::MinesweeperClock;
+use crate::hint::HintsUsed;
+use crate::stats::{ClickCounts, update_stats};
+use crate::cursor::REVEAL_KEY;
+
+// The window and the cameras, to find the tile under the mouse
+#[derive(SystemParam)]
+pub struct BoardPicker<'w, 's> {
+    windows: Query<'w, 's, &'static Window>,
+    cameras: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+}
+
+impl BoardPicker<'_, '_> {
+    // The tile of the board under the mouse, as seen through the active camera
+    pub fn coord(
+        &self,
+        board: &MinesweeperBoard,
+        board_transform: &GlobalTransform,
+    ) -> Option<Coord2D<i32>> {
+        let window = self.windows.get_single().ok()?;
+        let (camera, camera_transform) = self.cameras.iter().find(|(camera, _)| camera.is_active)?;
+        board.cursor_to_coord(window, camera, camera_transform, board_transform)
+    }
+}
+
+// Turns the mouse buttons used on the board into actions. Right cycles the cover as soon as it is
+// pressed, while a reveal with left or a chord is played when released
+pub fn mouse_actions(
+    picker: BoardPicker,
+    board: Query<(&MinesweeperBoard, &GlobalTransform)>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut cursor_moved: EventReader<CursorMoved>,
+    mut hovered: ResMut<HoveredCell>,
+    mut press: ResMut<MousePress>,
+    mut actions: EventWriter<MinesweeperAction>,
+) {
+    let (board, board_transform) = board.single();
+    let mouse_moved = cursor_moved.iter().count() > 0;
+    let coord = picker.coord(board, board_transform);
+
+    // Only follow the mouse when it moves, so that it doesn't take over from the keyboard cursor
+    if let Some(coord) = coord.filter(|_| mouse_moved) {
+        if hovered.0 != Some(coord) {
+            hovered.0 = Some(coord);
+        }
+    }
+
+    actions.send_batch(pressed_actions(&mouse_buttons, &mut press, coord));
+}
+
+// Follows the mouse buttons over the given tile, or off the board, and returns the actions played
+// this frame. A left press holds the tile down, and a chord (see is_chord) the tiles around it as
+// well. Releasing the buttons plays the move if the mouse is still over the tile it was pressed
+// on, and cancels it otherwise. The press is only changed when it is held or let go, so that the
+// tile views are only synced then
+pub fn pressed_actions(
+    mouse_buttons: &Input<MouseButton>,
+    press: &mut ResMut<MousePress>,
+    coord: Option<Coord2D<i32>>,
+) -> Vec<MinesweeperAction> {
+    let mut actions = Vec::new();
+
+    // A release can be missed, e.g. while the camera is panned, so the press is let go once no
+    // button is used anymore
+    let idle = mouse_buttons.get_pressed().len() == 0
+        && mouse_buttons.get_just_pressed().len() == 0
+        && mouse_buttons.get_just_released().len() == 0;
+    let mut next = if idle { MousePress::default() } else { **press };
 
-pub fn reveal_coord(
-    mut commands: Commands,
-    windows: Query<&Window>,
+    for &button in mouse_buttons.get_just_pressed() {
+        if is_chord(button, mouse_buttons) {
+            next = MousePress { coord, chord: true, held: false };
+            continue;
+        }
+        match button {
+            MouseButton::Left => next = MousePress { coord, chord: false, held: false },
+            MouseButton::Right => actions.extend(coord.map(MinesweeperAction::CycleCover)),
+            _ => (),
+        }
+    }
+    next.held = next.coord.is_some() && next.coord == coord;
+
+    // A chord ends when either of its buttons is released, after which the other does nothing
+    for &button in mouse_buttons.get_just_released() {
+        let ends_press = match button {
+            MouseButton::Left => true,
+            MouseButton::Right | MouseButton::Middle => next.chord,
+            _ => false,
+        };
+        let Some(pressed) = next.coord.filter(|_| ends_press) else { continue };
+        if next.held {
+            actions.push(if next.chord {
+                MinesweeperAction::Chord(pressed)
+            } else {
+                MinesweeperAction::Reveal(pressed)
+            });
+        }
+        next = MousePress::default();
+    }
+
+    if **press != next {
+        **press = next;
+    }
+    actions
+}
+
+// Plays the actions from the mouse and the keyboard on the minefield. The tile views follow the
+// minefield, so nothing is spawned here
+pub fn apply_actions(
     mut minefield: ResMut<Minefield>,
-    board: Query<&MinesweeperBoard>,
-    covered_tiles: Query<(Entity, &Cover, &Coord2D<i32>)>,
-    mut mouse_event: EventReader<MouseButtonInput>,
-    asset_server: Res<AssetServer>,
+    mut actions: EventReader<MinesweeperAction>,
+    mut clicks: ResMut<ClickCounts>,
 ) {
-    let window = windows.single();
-    let board = board.single();
-
-    for event in mouse_event.iter() {
-        if let Some(pos) = window.cursor_position() {
-            if let Some(coord) = board.mouse_to_coord(window, pos) {
-                if board.is_coord_valid(coord.0, coord.1) {
-                    handle_mouse_event(
-                        &mut commands,
-                        &mut minefield,
-                        board,
-                        &covered_tiles,
-                        &coord,
-                        event,
-                        &asset_server,
-                    );
-                }
+    for action in actions.iter() {
+        clicks.count(action);
+        apply_action(&mut minefield, action);
+    }
+}
+
+pub fn apply_action(minefield: &mut Minefield, action: &MinesweeperAction) {
+    match action {
+        // Reveal the tile and if it is empty, reveal all adjacent tiles. The first reveal also
+        // places the mines. A flag keeps its tile from being revealed
+        MinesweeperAction::Reveal(coord) => {
+            if minefield.cover(coord) != Cover::Flagged {
+                minefield.reveal_coord(coord);
             }
-        }
+        },
+        // Cycle the cover (flag, question mark, nothing) of a tile that is still covered
+        MinesweeperAction::CycleCover(coord) => {
+            if !minefield.is_revealed(coord) {
+                minefield.cycle_cover(coord);
+            }
+        },
+        // Reveal the unflagged neighbors of a number with as many flags around it
+        MinesweeperAction::Chord(coord) => {
+            minefield.chord(coord);
+        },
+    }
+}
+
+// A chord is a middle click, or the left and right mouse buttons pressed together
+fn is_chord(pressed_button: MouseButton, mouse_buttons: &Input<MouseButton>) -> bool {
+    match pressed_button {
+        MouseButton::Middle => true,
+        MouseButton::Left => mouse_buttons.pressed(MouseButton::Right),
+        MouseButton::Right => mouse_buttons.pressed(MouseButton::Left),
+        _ => false,
     }
 }
 
-// A function that uses the Minefields function "game_over" to set the next ArcadeState to
-// ArcadeState::Menu
+// A function that uses the Minefields function "game_over" to decide the game. The game is added
+// to the stats of the board and the result is stored for the popup. The board is revealed before
+// moving on to it. Games won with the help of hints don't count for the records, and practice
+// games are left out of the stats altogether, and so are games on imported boards. Endless runs keep
+// a best score instead of times. The lives left are told for games with more than one
 pub fn minesweeper_game_over(
-    commands: Commands,
     minefield: Res<Minefield>,
-    mut next_state: ResMut<NextState<ArcadeState>>,
+    clock: Res<MinesweeperClock>,
+    settings: Res<MinesweeperSettings>,
+    hints: Res<HintsUsed>,
+    clicks: Res<ClickCounts>,
+    mut result: ResMut<MinesweeperResult>,
+    mut next_phase: ResMut<NextState<MinesweeperPhase>>,
 ) {
-    match minefield.game_over() {
+    let seconds = clock.0.elapsed_secs();
+    let board_name = settings.board_name();
+    *result = match minefield.game_over() {
+        // An endless run always ends on a mine, and scores the tiles revealed before it
+        MinesweeperStatus::MineTriggered if minefield.is_endless() => {
+            let score = minefield.num_revealed_safe() as u32;
+            let is_best_score = update_stats(|stats| stats.record_run(&board_name, score));
+            MinesweeperResult {
+                header: "Run Over".to_string(),
+                body: format!(
+                    "You revealed {} safe tiles in {:.1} s{}\nUsing {}",
+                    score,
+                    seconds,
+                    if is_best_score { " - a new best score!" } else { "" },
+                    *clicks),
+                is_best_time: false,
+            }
+        },
+        MinesweeperStatus::MineTriggered if minefield.is_imported() => MinesweeperResult {
+            header: "Game Over".to_string(),
+            body: format!(
+                "You triggered a mine after {:.1} s\nImported boards are not recorded",
+                seconds),
+            is_best_time: false,
+        },
         MinesweeperStatus::MineTriggered => {
-            next_state.set(ArcadeState::Popup);
-            arcade_popup::spawn_popup(
-                commands,
-                "Game Over".to_string(),
-                "You triggered a mine".to_string(),
-            );
+            update_stats(|stats| stats.record_loss(&board_name));
+            MinesweeperResult {
+                header: "Game Over".to_string(),
+                body: format!("You triggered a mine after {:.1} s\nUsing {}", seconds, *clicks),
+                is_best_time: false,
+            }
+        },
+        MinesweeperStatus::GameWon if minefield.is_practice() => MinesweeperResult {
+            header: "Practice Done".to_string(),
+            body: format!(
+                "You cleared the practice board in {:.1} s\nPractice games are not recorded",
+                seconds),
+            is_best_time: false,
+        },
+        MinesweeperStatus::GameWon if minefield.is_imported() => MinesweeperResult {
+            header: "Congratulations".to_string(),
+            body: format!(
+                "You cleared the imported board in {:.1} s\nImported boards are not recorded",
+                seconds),
+            is_best_time: false,
         },
         MinesweeperStatus::GameWon => {
-            next_state.set(ArcadeState::Popup);
-            arcade_popup::spawn_popup(
-                commands,
-                "Congratulations".to_string(),
-                "You cleared all the mines".to_string(),
-            );
+            let scores = clicks.run_scores(minefield.three_bv(), seconds);
+            let counts_for_records = hints.0 == 0;
+            let is_best_time = update_stats(|stats| {
+                stats.record_win(&board_name, counts_for_records.then_some(scores))
+            });
+
+            let record = if !counts_for_records {
+                format!(" with {} hint(s), so it is not recorded", hints.0)
+            } else if is_best_time {
+                " - a new best time!".to_string()
+            } else {
+                String::new()
+            };
+            MinesweeperResult {
+                header: "Congratulations".to_string(),
+                body: format!(
+                    "You cleared all the mines in {:.1} s{}\n3BV {} at {:.2}/s, {:.0}% efficiency\nUsing {}",
+                    seconds,
+                    record,
+                    minefield.three_bv(),
+                    scores.three_bv_per_second,
+                    scores.efficiency,
+                    *clicks),
+                is_best_time,
+            }
         },
-        MinesweeperStatus::InProgress => (),
+        MinesweeperStatus::InProgress => return,
+    };
+    if let (Some(lives @ 2..), Some(lives_left)) = (minefield.lives(), minefield.lives_left()) {
+        result.body.push_str(&format!("\n{} of {} lives left", lives_left, lives));
     }
+    next_phase.set(MinesweeperPhase::Revealing);
 }
 
-// this function handles the mouse event after being called by reveal_coord.
-// If the left mousebutton is pressed, it should despawn the corresponding tile.
-// If the right mousebutton is pressed, it should flag the corresponding tile by despawning the
-// tile and spawning a flag sprite in its place.
-fn handle_mouse_event(
-    mut commands: &mut Commands,
-    mut minefield: &mut ResMut<Minefield>,
-    board: &MinesweeperBoard,
-    covered_tiles: &Query<(Entity, &Cover, &Coord2D<i32>)>,
-    pressed_coord: &Coord2D<i32>,
-    mouse_event: &MouseButtonInput,
-    asset_server: &Res<AssetServer>,
+// Shows the outcome on the board. On a loss the tile views uncover all mines, highlight the
+// triggered one and cross out wrong flags by themselves. On a win all remaining mines are flagged
+pub fn reveal_board(
+    mut minefield: ResMut<Minefield>,
+    mut reveal_timer: ResMut<RevealTimer>,
 ) {
-    for (entity, _, coord) in covered_tiles.iter() {
-        if coord == pressed_coord {
-            match (mouse_event.state, mouse_event.button) {
-                (ButtonState::Pressed, MouseButton::Left) => {
-                    reveal_and_despawn_from_coord(
-                        &mut commands,
-                        &mut minefield,
-                        &covered_tiles,
-                        &pressed_coord,
-                    );
-                    break;
-                },
-                (ButtonState::Pressed, MouseButton::Right) => {
-                    flag_coord(
-                        &mut commands,
-                        &mut minefield,
-                        board,
-                        &entity,
-                        &pressed_coord,
-                        &asset_server,
-                    );
-                    break;
-                },
-                _ => (),
-            }
-        }
+    reveal_timer.0.reset();
+
+    if minefield.game_over() == MinesweeperStatus::GameWon {
+        minefield.flag_remaining_mines();
     }
 }
 
-fn reveal_and_despawn_from_coord(
-    commands: &mut Commands,
-    minefield: &mut ResMut<Minefield>,
-    covered_tiles: &Query<(Entity, &Cover, &Coord2D<i32>)>,
-    pressed_coord: &Coord2D<i32>,
+// Lets go of the tiles held down with the mouse once the game is decided, since the board isn't
+// played anymore
+pub fn release_mouse_press(mut press: ResMut<MousePress>) {
+    *press = MousePress::default();
+}
+
+// Moves on to the popup when the board has been shown for a while, or on a click or reveal key
+pub fn show_result_popup(
+    commands: Commands,
+    time: Res<Time>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    result: Res<MinesweeperResult>,
+    mut reveal_timer: ResMut<RevealTimer>,
+    mut next_state: ResMut<NextState<ArcadeState>>,
 ) {
-    // Reveal the tile and if it is empty, reveal all adjacent tiles
-    let revealed_coords = minefield.reveal_coord(pressed_coord);
-    for (entity, _, coord) in covered_tiles.iter() {
-        if revealed_coords.contains(coord) {
-            commands.entity(entity).despawn_recursive();
-        }
+    if reveal_timer.0.tick(time.delta()).finished()
+        || mouse_buttons.get_just_pressed().len() > 0
+        || keys.just_pressed(REVEAL_KEY) {
+        next_state.set(ArcadeState::Popup);
+        arcade_popup::spawn_popup(
+            commands,
+            result.header.clone(),
+            result.body.clone(),
+        );
     }
 }
 
-fn flag_coord(
-    commands: &mut Commands,
-    minefield: &mut ResMut<Minefield>,
-    board: &MinesweeperBoard,
-    entity: &Entity,
-    pressed_coord: &Coord2D<i32>,
-    asset_server: &Res<AssetServer>,
+// Exports the board as it is now, so that it can be shared or attached to a bug report
+pub fn export_board(keys: Res<Input<KeyCode>>, minefield: Res<Minefield>) {
+    // The text format only holds bounded boards
+    if !keys.just_pressed(EXPORT_KEY) || !minefield.mines_placed() || minefield.is_endless() {
+        return;
+    }
+
+    let result = save_path("minesweeper", EXPORT_FILE).and_then(|path| {
+        std::fs::write(&path, to_text(&minefield, true))?;
+        Ok(path)
+    });
+    match result {
+        Ok(path) => bevy::log::info!("Exported the minesweeper board to {}", path.display()),
+        Err(e) => bevy::log::warn!("Could not export the minesweeper board: {}", e),
+    }
+}
+
+// Starts a new game on the board of the export file or of the MBF file, whichever was written last.
+// Revealed tiles, flags and question marks in the export are kept
+pub fn import_board(
+    keys: Res<Input<KeyCode>>,
+    mut imported: ResMut<ImportedBoard>,
+    mut next_state: ResMut<NextState<ArcadeState>>,
 ) {
-    // load flag sprite
-    let flag = asset_server.load("sprites/flag.png");
-    
-    // toggle flag at the given coord and if it is now flagged, spawn a flag sprite
-    let cover = minefield.toggle_flag(pressed_coord);
-    commands.entity(*entity).despawn_recursive();
-    commands.spawn(cover.clone())
-        .insert(
-            board.tile_sprite_at_coord(
-                pressed_coord.0,
-                pressed_coord.1,
-                3,
-                TILE_COLOR_COVERED)
-        )
-        .insert(*pressed_coord)
-        .with_children(|builder| {
-            builder.spawn(cover.to_sprite(flag.clone()));
-        });
+    if !keys.just_pressed(IMPORT_KEY) {
+        return;
+    }
+
+    let loaded = save_path("minesweeper", EXPORT_FILE).and_then(|text_path| {
+        let mbf_path = save_path("minesweeper", IMPORT_MBF_FILE)?;
+        let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let (use_mbf, skipped) = match (modified(&text_path), modified(&mbf_path)) {
+            (Some(text), Some(mbf)) if mbf > text => (true, Some(&text_path)),
+            (Some(_), Some(_)) => (false, Some(&mbf_path)),
+            (None, Some(_)) => (true, None),
+            _ => (false, None),
+        };
+        if let Some(skipped) = skipped {
+            bevy::log::info!("Skipped the older minesweeper board in {}", skipped.display());
+        }
+        if use_mbf {
+            from_mbf(&std::fs::read(&mbf_path)?).map(|board| (board, mbf_path))
+        } else {
+            from_text(&std::fs::read_to_string(&text_path)?).map(|board| (board, text_path))
+        }
+    });
+    match loaded {
+        Ok((board, path)) => {
+            bevy::log::info!("Imported the minesweeper board from {}", path.display());
+            imported.0 = Some(board.imported());
+            next_state.set(ArcadeState::PlayingMinesweeper);
+        },
+        Err(e) => bevy::log::warn!("Could not import a minesweeper board: {}", e),
+    }
+}
+
+pub fn reset_phase(mut next_phase: ResMut<NextState<MinesweeperPhase>>) {
+    next_phase.set(MinesweeperPhase::Playing);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arcade_util::Topology;
+
+    #[test]
+    fn flags_keep_their_tiles_from_being_revealed() {
+        let mine = Coord2D(0, 0);
+        let mut minefield = Minefield::from_mines(3, 3, Topology::Square, &[mine]).unwrap();
+        apply_action(&mut minefield, &MinesweeperAction::CycleCover(mine));
+        apply_action(&mut minefield, &MinesweeperAction::Reveal(mine));
+        assert!(!minefield.is_revealed(&mine));
+        assert_eq!(minefield.game_over(), MinesweeperStatus::InProgress);
+
+        // Once the flag is taken off, the tile can be revealed again
+        apply_action(&mut minefield, &MinesweeperAction::CycleCover(mine));
+        apply_action(&mut minefield, &MinesweeperAction::CycleCover(mine));
+        apply_action(&mut minefield, &MinesweeperAction::Reveal(mine));
+        assert_eq!(minefield.game_over(), MinesweeperStatus::MineTriggered);
+    }
 }
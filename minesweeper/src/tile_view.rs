@@ -0,0 +1,469 @@
+// This file contains the view of the minefield. Every tile is one entity for the whole game, with
+// an icon (flag, question mark or mine) and a number as children. When the minefield changes, the
+// tiles that look different are updated in place rather than spawned again.
+//
+// The tiles are grouped in square chunks, and chunks out of view of the camera are hidden, so that
+// only the visible part of a large board is drawn. An endless board gets its chunks when they
+// first come into view.
+
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::{
+    Commands,
+    Component,
+    Entity,
+    Resource,
+    Res,
+    ResMut,
+    Local,
+    Query,
+    Added,
+    With,
+    Without,
+    Children,
+    Handle,
+    Image,
+    Color,
+    Transform,
+    Visibility,
+    Vec2,
+    World,
+    FromWorld,
+    AssetServer,
+    BuildChildren,
+    ChildBuilder,
+    DetectChanges,
+    DetectChangesMut,
+    DespawnRecursiveExt,
+    SpatialBundle,
+    Camera,
+    GlobalTransform,
+    OrthographicProjection,
+    Ref,
+    Rect,
+    Mut,
+    default,
+};
+use bevy::ecs::system::SystemParam;
+use bevy::sprite::{Sprite, SpriteBundle};
+use bevy::text::{Font, Text, Text2dBundle, TextStyle};
+
+use arcade_util::{ArcadeCamera, Coord2D, visible_world_rect};
+
+use crate::board::MinesweeperBoard;
+use crate::explosion::{Explosion, explosion};
+use crate::minefield::Minefield;
+use crate::util::{
+    Cover,
+    Tile,
+    MinesweeperStatus,
+    MousePress,
+    TILE_SIZE,
+    TILE_COLOR,
+    TILE_COLOR_COVERED,
+    TILE_COLOR_TRIGGERED,
+    FONT_SIZE,
+    CHUNK_SIZE,
+    wrong_flag_cross,
+};
+
+// The tiles are drawn above the board, and their icons and numbers above the tiles
+const TILE_Z: i32 = 1;
+const CONTENT_Z: f32 = 1.0;
+
+type CameraViewQuery<'a> = (&'a Camera, Ref<'a, GlobalTransform>, Ref<'a, OrthographicProjection>);
+pub type TileViewQuery<'a> = (&'a mut TileView, &'a mut Sprite, &'a Children);
+pub type TileViewItem<'a> = (Mut<'a, TileView>, Mut<'a, Sprite>, &'a Children);
+type TileIconQuery<'a> = (&'a mut Handle<Image>, &'a mut Visibility);
+type TileNumberQuery<'a> = (&'a mut Text, &'a mut Visibility);
+type TileNumberFilter = (With<TileNumber>, Without<TileIcon>);
+pub type AddedTileViewFilter = (With<TileView>, Added<Coord2D<i32>>);
+
+// How a tile looks, which follows from the minefield and whether the game was lost
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TileLook {
+    Covered(Cover),
+    Revealed(Tile),
+    // A mine that was stepped on
+    Triggered,
+    // A flag on a tile without a mine, shown once the game is lost
+    WrongFlag,
+    // A covered tile held down with the mouse
+    Pressed,
+}
+
+impl TileLook {
+    pub fn of(minefield: &Minefield, coord: &Coord2D<i32>, lost: bool) -> Self {
+        let tile = minefield.tile(coord).unwrap_or(Tile::Empty);
+        let cover = minefield.cover(coord);
+
+        // Mines stepped on with lives to spare stay revealed
+        let stepped_on = minefield.is_revealed(coord) && tile == Tile::Mine;
+
+        if minefield.triggered_mine() == Some(*coord) || stepped_on {
+            TileLook::Triggered
+        } else if minefield.is_revealed(coord) {
+            TileLook::Revealed(tile)
+        } else if lost && tile == Tile::Mine && cover != Cover::Flagged {
+            TileLook::Revealed(Tile::Mine)
+        } else if lost && tile != Tile::Mine && cover == Cover::Flagged {
+            TileLook::WrongFlag
+        } else {
+            TileLook::Covered(cover)
+        }
+    }
+
+    // How the tile looks while held down with the mouse. Flags keep the tile from being revealed,
+    // so flagged tiles don't go down
+    pub fn held(self) -> Self {
+        match self {
+            TileLook::Covered(Cover::Unflagged | Cover::Questioned) => TileLook::Pressed,
+            look => look,
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            TileLook::Covered(_) | TileLook::WrongFlag => TILE_COLOR_COVERED,
+            TileLook::Revealed(_) | TileLook::Pressed => TILE_COLOR,
+            TileLook::Triggered => TILE_COLOR_TRIGGERED,
+        }
+    }
+
+    fn icon(&self, images: &TileImages) -> Option<Handle<Image>> {
+        match self {
+            TileLook::Covered(Cover::Flagged) | TileLook::WrongFlag => Some(images.flag.clone()),
+            TileLook::Covered(Cover::Questioned) => Some(images.question.clone()),
+            TileLook::Revealed(Tile::Mine) | TileLook::Triggered => Some(images.mine.clone()),
+            _ => None,
+        }
+    }
+
+    fn number(&self) -> Option<i32> {
+        match self {
+            TileLook::Revealed(Tile::Number(n)) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+// The look a tile view shows, none until it is first synced with the minefield
+#[derive(Component, Default)]
+pub struct TileView(Option<TileLook>);
+
+// A group of tiles, which are drawn only when the chunk is in view. The coordinate counts in chunks
+// from the one at the first tile, and the bounds are in the space of the board
+#[derive(Component)]
+pub struct TileChunk {
+    coord: Coord2D<i32>,
+    bounds: Rect,
+}
+
+// Tag component for the image on a tile
+#[derive(Component)]
+pub struct TileIcon;
+
+// Tag component for the number on a tile
+#[derive(Component)]
+pub struct TileNumber;
+
+// Tag component for the lines crossing out a wrong flag
+#[derive(Component)]
+pub struct WrongFlagCross;
+
+// The images and font of the tiles, loaded once
+#[derive(Resource)]
+pub struct TileImages {
+    flag: Handle<Image>,
+    question: Handle<Image>,
+    mine: Handle<Image>,
+    font: Handle<Font>,
+}
+
+impl FromWorld for TileImages {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        TileImages {
+            flag: asset_server.load("sprites/flag.png"),
+            question: asset_server.load("sprites/question.png"),
+            mine: asset_server.load("sprites/mine.png"),
+            font: asset_server.load("fonts/pixeled.ttf"),
+        }
+    }
+}
+
+// The color of the number of mines around a tile
+fn number_color(n: i32) -> Color {
+    match n {
+        1 => Color::rgb(0.0, 0.0, 1.0),
+        2 => Color::rgb(0.0, 0.5, 0.0),
+        3 => Color::rgb(1.0, 0.0, 0.0),
+        4 => Color::rgb(0.0, 0.0, 0.5),
+        5 => Color::rgb(0.5, 0.0, 0.0),
+        6 => Color::rgb(0.0, 0.5, 0.5),
+        7 => Color::rgb(0.5, 0.5, 0.0),
+        _ => Color::rgb(0.5, 0.0, 0.5),
+    }
+}
+
+// Spawns a view for every tile of the board, in chunks under the board entity. They start out
+// blank and get their look from the next sync
+pub fn spawn_tile_views(builder: &mut ChildBuilder, board: &MinesweeperBoard, images: &TileImages) {
+    let chunks_x = (board.get_width() + CHUNK_SIZE - 1) / CHUNK_SIZE;
+    let chunks_y = (board.get_height() + CHUNK_SIZE - 1) / CHUNK_SIZE;
+    for chunk_x in 0..chunks_x {
+        for chunk_y in 0..chunks_y {
+            spawn_tile_chunk(builder, board, images, Coord2D(chunk_x, chunk_y));
+        }
+    }
+}
+
+// Spawns the views of the tiles in a chunk, which is cut off at the edges of a bounded board
+fn spawn_tile_chunk(
+    builder: &mut ChildBuilder,
+    board: &MinesweeperBoard,
+    images: &TileImages,
+    coord: Coord2D<i32>,
+) {
+    let first = Coord2D(coord.0 * CHUNK_SIZE, coord.1 * CHUNK_SIZE);
+    let mut last = Coord2D(first.0 + CHUNK_SIZE - 1, first.1 + CHUNK_SIZE - 1);
+    if board.is_bounded() {
+        last = Coord2D(last.0.min(board.get_width() - 1), last.1.min(board.get_height() - 1));
+    }
+    // The rows next to the first and last ones are taken along for the shift of staggered rows
+    let bounds = [Coord2D(first.0, first.1 + 1), Coord2D(last.0, last.1 - 1)]
+        .into_iter()
+        .fold(
+            Rect::from_corners(
+                board.cell_pos_to_physical_pos(first),
+                board.cell_pos_to_physical_pos(last)),
+            |bounds, coord| bounds.union_point(board.cell_pos_to_physical_pos(coord)))
+        .inset(TILE_SIZE / 2.0);
+
+    builder.spawn(SpatialBundle::default())
+        .insert(TileChunk { coord, bounds })
+        .with_children(|builder| {
+            for x in first.0..=last.0 {
+                for y in first.1..=last.1 {
+                    spawn_tile_view(builder, board, images, Coord2D(x, y));
+                }
+            }
+        });
+}
+
+fn spawn_tile_view(
+    builder: &mut ChildBuilder,
+    board: &MinesweeperBoard,
+    images: &TileImages,
+    coord: Coord2D<i32>,
+) {
+    builder.spawn(board.tile_sprite_at_coord(coord.0, coord.1, TILE_Z, TILE_COLOR_COVERED))
+        .insert(coord)
+        .insert(TileView::default())
+        .with_children(|builder| {
+            builder.spawn(SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0.0, 0.0, CONTENT_Z),
+                visibility: Visibility::Hidden,
+                ..default()
+            })
+            .insert(TileIcon);
+
+            builder.spawn(Text2dBundle {
+                text: Text::from_section("", TextStyle {
+                    font: images.font.clone(),
+                    font_size: FONT_SIZE,
+                    color: Color::BLACK,
+                }),
+                transform: Transform::from_xyz(0.0, 0.0, CONTENT_Z),
+                visibility: Visibility::Hidden,
+                ..default()
+            })
+            .insert(TileNumber);
+        });
+}
+
+// Takes the part of the world the camera shows into the space of a board. Boards are only moved,
+// so their translation does so. The transforms are from the last frame, so the view is widened by
+// a chunk to keep the edges drawn while panning
+fn board_view(view: Rect, board_transform: &GlobalTransform) -> Rect {
+    let offset = board_transform.translation().truncate();
+    Rect::from_corners(view.min - offset, view.max - offset)
+        .inset(CHUNK_SIZE as f32 * TILE_SIZE)
+}
+
+// Spawns the chunks of an endless board which come into view for the first time. Chunks are kept
+// once spawned, and hidden again by the culling when out of view
+pub fn spawn_endless_chunks(
+    mut commands: Commands,
+    images: Res<TileImages>,
+    cameras: Query<CameraViewQuery, With<ArcadeCamera>>,
+    board: Query<(Entity, &MinesweeperBoard, &GlobalTransform)>,
+    chunks: Query<&TileChunk>,
+) {
+    let Ok((camera, camera_transform, projection)) = cameras.get_single() else { return };
+    let Ok((board_entity, board, board_transform)) = board.get_single() else { return };
+    let first_frame = chunks.is_empty();
+    if board.is_bounded()
+        || (!first_frame && !camera_transform.is_changed() && !projection.is_changed()) {
+        return;
+    }
+    let Some(view) = visible_world_rect(camera, &camera_transform) else { return };
+    let view = board_view(view, board_transform);
+
+    let spawned: HashSet<Coord2D<i32>> = chunks.iter().map(|chunk| chunk.coord).collect();
+    let to_chunk = |pos: Vec2| {
+        let Coord2D(x, y) = board.physical_pos_to_cell_pos(pos);
+        Coord2D(x.div_euclid(CHUNK_SIZE), y.div_euclid(CHUNK_SIZE))
+    };
+    let (min, max) = (to_chunk(view.min), to_chunk(view.max));
+    commands.entity(board_entity).with_children(|builder| {
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                if !spawned.contains(&Coord2D(x, y)) {
+                    spawn_tile_chunk(builder, board, &images, Coord2D(x, y));
+                }
+            }
+        }
+    });
+}
+
+// Hides the chunks which the camera doesn't show, whenever the camera moves or zooms, or a chunk
+// is spawned or moved along with its board
+pub fn cull_tile_chunks(
+    cameras: Query<CameraViewQuery, With<ArcadeCamera>>,
+    mut chunks: Query<(Ref<TileChunk>, Ref<GlobalTransform>, &mut Visibility)>,
+) {
+    let Ok((camera, camera_transform, projection)) = cameras.get_single() else { return };
+    let chunks_moved = chunks.iter()
+        .any(|(chunk, transform, _)| chunk.is_added() || transform.is_changed());
+    if !camera_transform.is_changed() && !projection.is_changed() && !chunks_moved {
+        return;
+    }
+    let Some(view) = visible_world_rect(camera, &camera_transform) else { return };
+
+    for (chunk, transform, mut visibility) in chunks.iter_mut() {
+        let in_view = !chunk.bounds.intersect(board_view(view, &transform)).is_empty();
+        let wanted = if in_view { Visibility::Inherited } else { Visibility::Hidden };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+}
+
+// The parts of the tile views that are updated when they get another look
+#[derive(SystemParam)]
+pub struct TileViewParts<'w, 's> {
+    commands: Commands<'w, 's>,
+    images: Res<'w, TileImages>,
+    icons: Query<'w, 's, TileIconQuery<'static>, With<TileIcon>>,
+    numbers: Query<'w, 's, TileNumberQuery<'static>, TileNumberFilter>,
+    crosses: Query<'w, 's, (), With<WrongFlagCross>>,
+    explosions: Query<'w, 's, (), With<Explosion>>,
+}
+
+impl TileViewParts<'_, '_> {
+    // Shows the look on a tile view, if it doesn't show it already. A mine explodes when it is
+    // stepped on, but not when a view is spawned for a game in progress. The cross of a wrong flag
+    // and an explosion are taken off when the tile gets another look, as when a replay goes back
+    pub fn show(&mut self, entity: Entity, look: TileLook, view: TileViewItem) {
+        let (mut view, mut sprite, children) = view;
+        if view.0 == Some(look) {
+            return;
+        }
+        let stepped_on = look == TileLook::Triggered && matches!(view.0, Some(TileLook::Covered(_)));
+        view.0 = Some(look);
+        sprite.color = look.color();
+
+        for child in children.iter() {
+            if let Ok((mut texture, mut visibility)) = self.icons.get_mut(*child) {
+                match look.icon(&self.images) {
+                    Some(icon) => {
+                        *texture = icon;
+                        *visibility = Visibility::Inherited;
+                    },
+                    None => *visibility = Visibility::Hidden,
+                }
+            }
+            if let Ok((mut text, mut visibility)) = self.numbers.get_mut(*child) {
+                match look.number() {
+                    Some(n) => {
+                        text.sections[0].value = n.to_string();
+                        text.sections[0].style.color = number_color(n);
+                        *visibility = Visibility::Inherited;
+                    },
+                    None => *visibility = Visibility::Hidden,
+                }
+            }
+            if self.crosses.contains(*child) || self.explosions.contains(*child) {
+                self.commands.entity(*child).despawn_recursive();
+            }
+        }
+
+        if look == TileLook::WrongFlag {
+            self.commands.entity(entity).with_children(|builder| {
+                for line in wrong_flag_cross() {
+                    builder.spawn(line).insert(WrongFlagCross);
+                }
+            });
+        }
+        if stepped_on {
+            self.commands.entity(entity).with_children(|builder| {
+                builder.spawn(explosion());
+            });
+        }
+    }
+}
+
+// The tiles held down by a mouse press: the pressed tile, and the tiles around it for a chord
+pub fn held_tiles(press: &MousePress, minefield: &Minefield) -> HashSet<Coord2D<i32>> {
+    let Some(coord) = press.coord.filter(|_| press.held) else { return HashSet::new() };
+    let mut held = HashSet::from([coord]);
+    if press.chord {
+        held.extend(minefield.neighbors(&coord));
+    }
+    held
+}
+
+// Updates the tiles which look different since the minefield or the mouse press last changed: the
+// tiles the minefield marked as changed, and the ones held down now or before. New views are synced
+// right away, so that a board can be spawned for a game in progress. The views are found by their
+// coordinate, which the views of a board that is gone may still be listed under
+pub fn sync_tile_views(
+    mut minefield: ResMut<Minefield>,
+    press: Res<MousePress>,
+    added: Query<(Entity, &Coord2D<i32>), AddedTileViewFilter>,
+    mut views: Query<TileViewQuery>,
+    mut parts: TileViewParts,
+    mut view_entities: Local<HashMap<Coord2D<i32>, Entity>>,
+    mut last_held: Local<HashSet<Coord2D<i32>>>,
+) {
+    if !minefield.is_changed() && !press.is_changed() && added.is_empty() {
+        return;
+    }
+    let lost = minefield.game_over() == MinesweeperStatus::MineTriggered;
+    let held = held_tiles(&press, &minefield);
+
+    // Taking the changes doesn't count as a change of the minefield, or every frame would be one
+    let changed = minefield.bypass_change_detection().take_changed();
+    let mut to_sync: HashSet<Coord2D<i32>> = match changed {
+        Some(changed) => changed,
+        None => view_entities.keys().copied().collect(),
+    };
+    for (entity, coord) in added.iter() {
+        view_entities.insert(*coord, entity);
+        to_sync.insert(*coord);
+    }
+    to_sync.extend(held.iter().chain(last_held.iter()));
+
+    for coord in to_sync.iter() {
+        let Some(&entity) = view_entities.get(coord) else { continue };
+        let Ok(view) = views.get_mut(entity) else { continue };
+        let look = TileLook::of(&minefield, coord, lost);
+        parts.show(entity, if held.contains(coord) { look.held() } else { look }, view);
+    }
+    *last_held = held;
+}
@@ -1,16 +1,49 @@
-use bevy::prelude::{Color, Component, Handle, default, Image, Transform, Vec2};
+use std::time::Duration;
+
+use bevy::prelude::{Color, Resource, States, Quat, default, KeyCode, Transform, Vec2};
 use bevy::sprite::{SpriteBundle, Sprite};
-use bevy::text::{TextStyle, Font, Text2dBundle, Text};
+use bevy::time::{Timer, TimerMode};
+
+use arcade_util::Coord2D;
 
 pub const TILE_SIZE: f32 = 30.0;
 pub const TILE_SPACING: f32 = 1.0;
 pub const TILE_COLOR: Color = Color::rgb(0.6, 0.6, 0.6);
 pub const TILE_COLOR_COVERED: Color = Color::rgb(0.4, 0.4, 0.4);
+pub const TILE_COLOR_TRIGGERED: Color = Color::rgb(0.9, 0.1, 0.1);
+pub const WRONG_FLAG_COLOR: Color = Color::rgb(0.9, 0.1, 0.1);
+
+// How long the revealed board is shown before the result popup appears
+pub const REVEAL_DURATION: Duration = Duration::from_millis(2500);
+
+// Bounds on the re-rolls when looking for a board that can be solved without guessing. An expert
+// board usually takes a few dozen. The solver is also bounded by the tiles it looks at over all
+// attempts, about a second of work, so that a large board doesn't hold up the first reveal. Both
+// are counted rather than timed, so the layout still only depends on the seed
+pub const NO_GUESS_MAX_ATTEMPTS: usize = 200;
+pub const NO_GUESS_WORK_BUDGET: usize = 4_000_000;
+
+// Writes the current board, including what has been revealed, to the minesweeper save folder
+pub const EXPORT_KEY: KeyCode = KeyCode::E;
+pub const EXPORT_FILE: &str = "board.txt";
+
+// Starts a new game on the board in the export file or in the MBF file, whichever was written last,
+// both in the minesweeper save folder. That is how shared boards are played
+pub const IMPORT_KEY: KeyCode = KeyCode::O;
+pub const IMPORT_MBF_FILE: &str = "board.mbf";
 
 pub const FONT_SIZE: f32 = 40.0;
 
-pub const BOARD_SIZE: i32 = 20;
-pub const NUM_MINES: i32 = 60;
+// The number of tiles along each side of a chunk, in which endless minefields are generated and
+// the tile views are culled
+pub const CHUNK_SIZE: i32 = 16;
+
+// Endless minefields have no mines within this many tiles from the origin, where the run starts
+pub const ENDLESS_START_RADIUS: i32 = 2;
+
+// With fewer mines than this, the empty tiles of an endless minefield can join up without end, and
+// revealing one of them would never finish
+pub const ENDLESS_MIN_DENSITY: f64 = 0.15;
 
 // Enum to describe different tile types
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -20,91 +53,81 @@ pub enum Tile {
     Empty,
 }
 
+// A move on the board, sent by both the mouse and the keyboard so that they are played the same way
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MinesweeperAction {
+    Reveal(Coord2D<i32>),
+    CycleCover(Coord2D<i32>),
+    Chord(Coord2D<i32>),
+}
+
+// The tile last pointed at, either by the mouse or by the keyboard cursor
+#[derive(Resource, Default)]
+pub struct HoveredCell(pub Option<Coord2D<i32>>);
+
+// The tile a mouse button went down on, which is played when the button is released over it, and
+// whether it is held for a chord. It is only held down while the mouse is still over it, so moving
+// off the tile before releasing cancels the move
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MousePress {
+    pub coord: Option<Coord2D<i32>>,
+    pub chord: bool,
+    pub held: bool,
+}
+
 // The minesweeper status enum
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum MinesweeperStatus {
     InProgress,
     MineTriggered,
     GameWon,
 }
 
-// The component identifying a Mine tile
-#[derive(Component)]
-pub struct Mine;
-
-impl Mine {
-    pub fn to_sprite(&self, mine_image: Handle<Image>) -> SpriteBundle {
-        SpriteBundle {
-            sprite: Sprite {
-                custom_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
-                ..default()
-            },
-            transform: Transform::from_xyz(0.0, 0.0, 1.0),
-            texture: mine_image,
-            ..default()
-        }
-    }
+// The phases of a game of minesweeper. After the game is decided, the board is revealed for a
+// while before moving on to the result popup
+#[derive(States, Clone, Copy, Default, Eq, PartialEq, Debug, Hash)]
+pub enum MinesweeperPhase {
+    #[default]
+    Playing,
+    Revealing,
 }
 
-// The component identifying a Mine's Neighbor
-#[derive(Component)]
-pub struct MineNeighbor(pub i32);
-
-impl MineNeighbor {
-    pub fn to_sprite(&self, font: Handle<Font>) -> Text2dBundle {
-        let (text, color) = match self {
-            MineNeighbor(1) => ("1", Color::rgb(0.0, 0.0, 1.0)),
-            MineNeighbor(2) => ("2", Color::rgb(0.0, 0.5, 0.0)),
-            MineNeighbor(3) => ("3", Color::rgb(1.0, 0.0, 0.0)),
-            MineNeighbor(4) => ("4", Color::rgb(0.0, 0.0, 0.5)),
-            MineNeighbor(5) => ("5", Color::rgb(0.5, 0.0, 0.0)),
-            MineNeighbor(6) => ("6", Color::rgb(0.0, 0.5, 0.5)),
-            MineNeighbor(7) => ("7", Color::rgb(0.5, 0.5, 0.0)),
-            MineNeighbor(8) => ("8", Color::rgb(0.5, 0.0, 0.5)),
-            _ => ("", Color::rgb(0.0, 0.0, 0.0)),
-        };
-
-        Text2dBundle {
-            text: Text::from_section(
-                text,
-                TextStyle {
-                    font,
-                    font_size: FONT_SIZE,
-                    color
-                }),
-            transform: Transform::from_xyz(0.0, 0.0, 1.0),
-            ..default()
-        }
+// The result of the last game, shown in the popup once the revealing phase is over
+#[derive(Resource, Default)]
+pub struct MinesweeperResult {
+    pub header: String,
+    pub body: String,
+    pub is_best_time: bool,
+}
+
+// Counts down how long the revealed board is shown
+#[derive(Resource)]
+pub struct RevealTimer(pub Timer);
+
+impl Default for RevealTimer {
+    fn default() -> Self {
+        RevealTimer(Timer::new(REVEAL_DURATION, TimerMode::Once))
     }
 }
 
-// The component identifying a Covered tile
-#[derive(Component, Clone)]
+// The cover on a tile which has not been revealed
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Cover {
     Flagged,
+    Questioned,
     Unflagged,
 }
 
-impl Cover {
-    pub fn to_sprite(&self, flag_image: Handle<Image>) -> SpriteBundle {
-        match self {
-            Cover::Flagged => self.flagged_sprite(flag_image),
-            Cover::Unflagged => self.unflagged_sprite(),
-        }
-    }
-
-    fn flagged_sprite(&self, flag_image: Handle<Image>) -> SpriteBundle {
-        SpriteBundle {
-            sprite: Sprite {
-                custom_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
-                ..default()
-            },
-            transform: Transform::from_xyz(0.0, 0.0, 3.0),
-            texture: flag_image,
+// Two crossed lines put on top of a flag which turned out not to cover a mine
+pub fn wrong_flag_cross() -> [SpriteBundle; 2] {
+    [1.0, -1.0].map(|direction: f32| SpriteBundle {
+        sprite: Sprite {
+            color: WRONG_FLAG_COLOR,
+            custom_size: Some(Vec2::new(TILE_SIZE * 1.2, 3.0)),
             ..default()
-        }
-    }
-
-    fn unflagged_sprite(&self) -> SpriteBundle {
-        SpriteBundle::default()
-    }
+        },
+        transform: Transform::from_xyz(0.0, 0.0, 4.0)
+            .with_rotation(Quat::from_rotation_z(direction * std::f32::consts::FRAC_PI_4)),
+        ..default()
+    })
 }
@@ -0,0 +1,387 @@
+// This file contains versus mode, in which two players race on copies of the same board: one with
+// the keyboard cursor on the left board, the other with the mouse on the right board. Stepping on a
+// mine costs time instead of the game, and the first to clear the board on their own clock wins.
+
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::{
+    Commands,
+    Component,
+    Resource,
+    Res,
+    ResMut,
+    Query,
+    With,
+    Without,
+    Entity,
+    Input,
+    KeyCode,
+    MouseButton,
+    NextState,
+    GlobalTransform,
+    Transform,
+    Children,
+    AssetServer,
+    Color,
+    Vec2,
+    BuildChildren,
+    NodeBundle,
+    TextBundle,
+    DetectChanges,
+    DetectChangesMut,
+    Local,
+    default,
+};
+use bevy::ecs::system::SystemParam;
+use bevy::text::{Text, TextStyle};
+use bevy::time::Time;
+use bevy::ui::{Style, Size, Val, UiRect, JustifyContent, AlignItems};
+use bevy::window::Window;
+
+use arcade_util::{ArcadeState, Coord2D, FitCameraQuery, MinesweeperSettings, fit_camera};
+
+use crate::board::{MinesweeperBoard, BOARD_MARGIN, spawn_board_entities};
+use crate::cursor::{MinesweeperCursor, cursor_actions, spawn_cursor_on};
+use crate::hud::MinesweeperClock;
+use crate::minefield::Minefield;
+use crate::systems::{BoardPicker, apply_action, pressed_actions};
+use crate::tile_view::{
+    AddedTileViewFilter,
+    TileChunk,
+    TileImages,
+    TileLook,
+    TileViewParts,
+    TileViewQuery,
+    held_tiles,
+};
+use crate::util::{MinesweeperStatus, MousePress};
+
+// Seconds added to the clock of a player for every mine stepped on
+pub const MINE_PENALTY_SECONDS: f32 = 10.0;
+
+// Room between the two boards
+const BOARD_GAP: f32 = 60.0;
+
+const HUD_TEXT_COLOR: Color = Color::rgb(0.9, 0.1, 0.1);
+
+type VersusBoardQuery<'a> = (Entity, &'a MinesweeperBoard, &'a VersusPlayer);
+
+// The two players, each on their own board
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VersusPlayer {
+    Keyboard,
+    Mouse,
+}
+
+impl VersusPlayer {
+    const ALL: [VersusPlayer; 2] = [VersusPlayer::Keyboard, VersusPlayer::Mouse];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            VersusPlayer::Keyboard => "Keyboard",
+            VersusPlayer::Mouse => "Mouse",
+        }
+    }
+}
+
+// The minefields of both players, and their times once they cleared them. The time played is kept
+// by the minesweeper clock, which runs until the match is decided
+#[derive(Resource, Default)]
+pub struct VersusMatch {
+    minefields: [Minefield; 2],
+    cleared: [Option<f32>; 2],
+}
+
+impl VersusMatch {
+    fn minefield(&self, player: VersusPlayer) -> &Minefield {
+        &self.minefields[player.index()]
+    }
+
+    fn penalty(&self, player: VersusPlayer) -> f32 {
+        self.minefield(player).mines_hit() as f32 * MINE_PENALTY_SECONDS
+    }
+
+    // The clock of a player, which is the time played plus the penalties until the board is cleared
+    fn time(&self, player: VersusPlayer, seconds: f32) -> f32 {
+        self.cleared[player.index()].unwrap_or(seconds + self.penalty(player))
+    }
+
+    // The match is decided once a player has cleared the board in a time the other can't beat
+    fn is_decided(&self, seconds: f32) -> bool {
+        let Some(best) = self.cleared.iter().flatten().copied().reduce(f32::min) else { return false };
+        VersusPlayer::ALL.iter().all(|player| self.time(*player, seconds) >= best)
+    }
+
+    // The player that cleared the board in the least time, if there is one
+    fn winner(&self) -> Option<VersusPlayer> {
+        match self.cleared {
+            [Some(keyboard), Some(mouse)] if keyboard == mouse => None,
+            [Some(keyboard), Some(mouse)] if keyboard > mouse => Some(VersusPlayer::Mouse),
+            [Some(_), _] => Some(VersusPlayer::Keyboard),
+            [None, Some(_)] => Some(VersusPlayer::Mouse),
+            [None, None] => None,
+        }
+    }
+}
+
+// Tag component for the root of the versus HUD
+#[derive(Component)]
+pub struct VersusHud;
+
+// Text showing the clock of a player
+#[derive(Component)]
+pub struct VersusClockText(VersusPlayer);
+
+// Deals both players the same board and spawns a board for each, with the HUD above them
+pub fn start_versus(
+    mut commands: Commands,
+    settings: Res<MinesweeperSettings>,
+    images: Res<TileImages>,
+    asset_server: Res<AssetServer>,
+    mut versus: ResMut<VersusMatch>,
+    mut clock: ResMut<MinesweeperClock>,
+) {
+    *versus = VersusMatch {
+        minefields: Minefield::versus(&settings),
+        cleared: [None, None],
+    };
+    clock.0.reset();
+
+    for player in VersusPlayer::ALL {
+        let board = spawn_board_entities(&mut commands, versus.minefield(player), &images);
+        commands.entity(board).insert(player);
+    }
+
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/pixeled.ttf"),
+        font_size: 20.0,
+        color: HUD_TEXT_COLOR,
+    };
+    commands.spawn(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Px(80.0)),
+            justify_content: JustifyContent::SpaceAround,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        ..default()
+    })
+    .insert(VersusHud)
+    .with_children(|parent| {
+        for player in VersusPlayer::ALL {
+            parent.spawn(TextBundle::from_section("", text_style.clone())
+                .with_style(Style {
+                    margin: UiRect::horizontal(Val::Px(20.0)),
+                    ..default()
+                }))
+                .insert(VersusClockText(player));
+        }
+    });
+}
+
+// Puts the boards next to each other, with the keyboard player on the left, and zooms the camera
+// out to fit both. The keyboard cursor is shown right away, since that player can't use the mouse
+pub fn place_versus_boards(
+    mut commands: Commands,
+    windows: Query<&Window>,
+    mut cursor: ResMut<MinesweeperCursor>,
+    mut boards: Query<(VersusBoardQuery, &mut Transform)>,
+    mut camera: Query<FitCameraQuery, Without<MinesweeperBoard>>,
+) {
+    let mut size = Vec2::ZERO;
+    for ((entity, board, player), mut transform) in boards.iter_mut() {
+        let width = board.get_physical_size().x;
+        let side = if *player == VersusPlayer::Keyboard { -1.0 } else { 1.0 };
+        transform.translation.x = side * (width + BOARD_GAP) / 2.0;
+        size = Vec2::new(2.0 * width + BOARD_GAP, board.get_physical_size().y);
+
+        if *player == VersusPlayer::Keyboard {
+            spawn_cursor_on(&mut commands, &mut cursor, entity, board);
+            cursor.shown = true;
+        }
+    }
+
+    let Ok(window) = windows.get_single() else { return };
+    for (mut camera, mut transform, mut projection) in camera.iter_mut() {
+        fit_camera(window, size, BOARD_MARGIN, (&mut camera, &mut transform, &mut projection));
+    }
+}
+
+// Plays the keyboard player's board with the cursor keys
+pub fn versus_keyboard_actions(
+    keys: Res<Input<KeyCode>>,
+    mut cursor: ResMut<MinesweeperCursor>,
+    boards: Query<VersusBoardQuery>,
+    mut versus: ResMut<VersusMatch>,
+) {
+    let Some((_, board, player)) = boards.iter()
+        .find(|(_, _, player)| **player == VersusPlayer::Keyboard) else { return };
+    cursor_actions(&keys, &mut cursor, board, |action| {
+        apply_action(&mut versus.minefields[player.index()], &action);
+    });
+}
+
+// Plays the mouse player's board with the mouse buttons
+pub fn versus_mouse_actions(
+    picker: BoardPicker,
+    boards: Query<(&MinesweeperBoard, &GlobalTransform, &VersusPlayer)>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut press: ResMut<MousePress>,
+    mut versus: ResMut<VersusMatch>,
+) {
+    let Some((board, board_transform, player)) = boards.iter()
+        .find(|(_, _, player)| **player == VersusPlayer::Mouse) else { return };
+    let coord = picker.coord(board, board_transform);
+
+    for action in pressed_actions(&mouse_buttons, &mut press, coord) {
+        apply_action(&mut versus.minefields[player.index()], &action);
+    }
+}
+
+// The boards of the match and the chunks holding their tile views
+#[derive(SystemParam)]
+pub struct VersusBoardViews<'w, 's> {
+    added: Query<'w, 's, (), AddedTileViewFilter>,
+    boards: Query<'w, 's, (&'static VersusPlayer, &'static Children)>,
+    chunks: Query<'w, 's, &'static Children, With<TileChunk>>,
+}
+
+// The tile view of every tile of each board and the tiles that were held down when they were last
+// synced, so that they are let go of
+#[derive(Default)]
+pub struct VersusSyncedViews {
+    entities: [HashMap<Coord2D<i32>, Entity>; 2],
+    held: [HashSet<Coord2D<i32>>; 2],
+}
+
+// Updates the tiles of each board from the minefield of its player, with the tiles held down by the
+// mouse on the board of the mouse player. Every tile is only gone over when the boards are spawned,
+// after that only the tiles changed in each minefield and the held ones are
+pub fn sync_versus_views(
+    mut versus: ResMut<VersusMatch>,
+    press: Res<MousePress>,
+    boards: VersusBoardViews,
+    mut views: Query<(&Coord2D<i32>, TileViewQuery)>,
+    mut parts: TileViewParts,
+    mut synced: Local<VersusSyncedViews>,
+) {
+    if !versus.is_changed() && !press.is_changed() && boards.added.is_empty() {
+        return;
+    }
+    let full_pass = !boards.added.is_empty();
+
+    for (player, board_children) in boards.boards.iter() {
+        let index = player.index();
+        // Taking the changes doesn't count as a change of the match, or every frame would be one
+        let changed = versus.bypass_change_detection().minefields[index].take_changed();
+        let minefield = versus.minefield(*player);
+        let held = match player {
+            VersusPlayer::Mouse => held_tiles(&press, minefield),
+            VersusPlayer::Keyboard => HashSet::new(),
+        };
+
+        if full_pass {
+            synced.entities[index].clear();
+            for tiles in boards.chunks.iter_many(board_children.iter()) {
+                for &entity in tiles.iter() {
+                    let Ok((coord, view)) = views.get_mut(entity) else { continue };
+                    synced.entities[index].insert(*coord, entity);
+                    let look = TileLook::of(minefield, coord, false);
+                    parts.show(entity, if held.contains(coord) { look.held() } else { look }, view);
+                }
+            }
+        } else {
+            let mut to_sync: HashSet<Coord2D<i32>> = match changed {
+                Some(changed) => changed,
+                None => synced.entities[index].keys().copied().collect(),
+            };
+            to_sync.extend(held.iter().chain(synced.held[index].iter()));
+
+            for coord in to_sync.iter() {
+                let Some(&entity) = synced.entities[index].get(coord) else { continue };
+                let Ok((_, view)) = views.get_mut(entity) else { continue };
+                let look = TileLook::of(minefield, coord, false);
+                parts.show(entity, if held.contains(coord) { look.held() } else { look }, view);
+            }
+        }
+        synced.held[index] = held;
+    }
+}
+
+// Runs the clock of the match until it is decided
+pub fn tick_versus_clock(
+    time: Res<Time>,
+    versus: Res<VersusMatch>,
+    mut clock: ResMut<MinesweeperClock>,
+) {
+    if !versus.is_decided(clock.0.elapsed_secs()) {
+        clock.0.tick(time.delta());
+    }
+}
+
+pub fn update_versus_hud(
+    versus: Res<VersusMatch>,
+    clock: Res<MinesweeperClock>,
+    mut texts: Query<(&mut Text, &VersusClockText)>,
+) {
+    let seconds = clock.0.elapsed_secs();
+    for (mut text, VersusClockText(player)) in texts.iter_mut() {
+        let status = if versus.cleared[player.index()].is_some() { "cleared" } else { "playing" };
+        text.sections[0].value = format!(
+            "{} {:05.1} s\n{} mine(s) hit, {}",
+            player.name(),
+            versus.time(*player, seconds),
+            versus.minefield(*player).mines_hit(),
+            status);
+    }
+}
+
+// Stops the clock of a player who cleared the board, and shows the result once the match is
+// decided
+pub fn versus_game_over(
+    commands: Commands,
+    clock: Res<MinesweeperClock>,
+    mut versus: ResMut<VersusMatch>,
+    mut next_state: ResMut<NextState<ArcadeState>>,
+) {
+    let seconds = clock.0.elapsed_secs();
+    for player in VersusPlayer::ALL {
+        let index = player.index();
+        if versus.cleared[index].is_none()
+            && versus.minefields[index].game_over() == MinesweeperStatus::GameWon {
+            let time = seconds + versus.penalty(player);
+            versus.cleared[index] = Some(time);
+            versus.minefields[index].flag_remaining_mines();
+        }
+    }
+    if !versus.is_decided(seconds) {
+        return;
+    }
+
+    let header = match versus.winner() {
+        Some(player) => format!("{} player wins", player.name()),
+        None => "It's a draw".to_string(),
+    };
+    let body = VersusPlayer::ALL.iter()
+        .map(|player| match versus.cleared[player.index()] {
+            Some(time) => format!(
+                "{}: cleared in {:.1} s with {} mine(s) hit",
+                player.name(), time, versus.minefield(*player).mines_hit()),
+            None => format!("{}: did not finish", player.name()),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    next_state.set(ArcadeState::Popup);
+    arcade_popup::spawn_popup(commands, header, body);
+}
+
+// Gives up the match and goes back to the menu
+pub fn leave_versus(keys: Res<Input<KeyCode>>, mut next_state: ResMut<NextState<ArcadeState>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(ArcadeState::Menu);
+    }
+}
@@ -1,6 +1,6 @@
 use bevy::prelude::App;
 
-use snake::SnakePlugin;
+use snake::{SnakePlugin, TronPlugin};
 use minesweeper::MinesweeperPlugin;
 use arcade_menu::MenuPlugin;
 use arcade_util::DefaultArcadePlugin;
@@ -14,6 +14,7 @@ fn main() {
         // Has to add MenuPlugin before SnakePlugin because the states are defined in MenuPlugin
         .add_plugin(MenuPlugin)
         .add_plugin(SnakePlugin)
+        .add_plugin(TronPlugin)
         .add_plugin(MinesweeperPlugin)
         .run();
 }
@@ -1,7 +1,7 @@
 use std::ops::Deref;
 
 use arcade_util::{DiscreteBoard, Collidable};
-use bevy::prelude::{Color, Component, Commands, Vec2, default, BuildChildren};
+use bevy::prelude::{Color, Component, Commands, default, BuildChildren};
 use bevy::sprite::{SpriteBundle, Sprite};
 
 use crate::util::{TILE_SIZE, TILE_SPACING, BOARD_SIZE};
@@ -37,9 +37,7 @@ pub fn spawn_board(mut commands: Commands) {
     commands.spawn(SpriteBundle {
         sprite: Sprite {
             color: BOARD_COLOR,
-            custom_size: Some(Vec2::new(
-                    board.get_physical_size(),
-                    board.get_physical_size())),
+            custom_size: Some(board.get_physical_size()),
             ..default()
         },
         ..default()
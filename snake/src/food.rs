@@ -1,22 +1,71 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use arcade_util::{Coord2D, CoordConfiguration};
-use bevy::prelude::{Component, World, Color, Commands, Plugin, App, EventReader, Query, Res};
+use bevy::prelude::{Component, World, Color, Commands, Plugin, App, EventReader, Query, Res, ResMut, Resource};
 use bevy::ecs::system::Command;
-use rand::seq::SliceRandom;
+use rand::{SeedableRng, rngs::SmallRng, seq::SliceRandom};
 
 use crate::board::SnakeBoard;
 use crate::snake::Snake;
 
 const FOOD_COLOR: Color = Color::RED;
+pub const FIRST_FOOD: Coord2D<i32> = Coord2D(10, 10);
 
 #[derive(Component)]
 pub struct Food;
 
+// The seed deciding where food shows up. Runs on the same seed get the same food as long as the
+// snake takes the same path. Defaults to a new seed every day.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct SnakeSeed(pub u64);
+
+impl Default for SnakeSeed {
+    fn default() -> Self {
+        let days = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since_epoch| since_epoch.as_secs() / (24 * 60 * 60))
+            .unwrap_or(0);
+        SnakeSeed(days)
+    }
+}
+
+#[derive(Resource)]
+pub struct FoodRng(pub SmallRng);
+
+impl FoodRng {
+    pub fn from_seed(seed: &SnakeSeed) -> Self {
+        FoodRng(SmallRng::seed_from_u64(seed.0))
+    }
+}
+
+impl Default for FoodRng {
+    fn default() -> Self {
+        FoodRng::from_seed(&SnakeSeed::default())
+    }
+}
+
+// Pick up to num_food new food coordinates which are not covered by the snake
+pub fn choose_food_coords(
+    rng: &mut SmallRng,
+    board_size: i32,
+    snake: &Snake,
+    num_food: usize,
+) -> Vec<Coord2D<i32>> {
+    let feasible_food_coord: Vec<Coord2D<i32>> = (0..board_size)
+        .flat_map(|x| (0..board_size)
+             .map(move |y| Coord2D(x, y)))
+        .filter(|c| !snake.configuration().any(|sc| sc == c))
+        .collect();
+
+    feasible_food_coord.choose_multiple(rng, num_food).copied().collect()
+}
+
 pub struct SpawnFood(pub Coord2D<i32>);
 
 impl Command for SpawnFood {
     fn write(self, world: &mut World) {
         let board = world.query::<&SnakeBoard>()
-            .iter(&world)
+            .iter(world)
             .next()
             .unwrap();
 
@@ -33,6 +82,8 @@ pub struct FoodPlugin;
 impl Plugin for FoodPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<NewFoodEvent>()
+            .init_resource::<SnakeSeed>()
+            .init_resource::<FoodRng>()
             .add_system(food_event_listener);
     }
 }
@@ -41,8 +92,11 @@ pub struct NewFoodEvent;
 
 pub fn spawn_food(
     mut commands: Commands,
+    seed: Res<SnakeSeed>,
+    mut rng: ResMut<FoodRng>,
 ) {
-    commands.add(SpawnFood(Coord2D(10, 10)));
+    *rng = FoodRng::from_seed(&seed);
+    commands.add(SpawnFood(FIRST_FOOD));
 }
 
 pub fn food_event_listener(
@@ -50,22 +104,20 @@ pub fn food_event_listener(
     query: Query<&SnakeBoard>,
     mut events: EventReader<NewFoodEvent>,
     snake: Res<Snake>,
+    mut rng: ResMut<FoodRng>,
 ) {
     if let Ok(board) = query.get_single() {  // To avoid panicing when we add the FoodPlugin
-        let feasible_food_coord: Vec<Coord2D<i32>> = (0..board.get_size())
-            .flat_map(|x| (0..board.get_size())
-                 .map(move |y| Coord2D(x, y)))
-            .filter(|c| !snake.configuration().any(|sc| sc == c))
-            .collect();
-
         // Need to do this to consume the events in the EventReader, and also add that much new food
         let mut num_food = 0;
         for _ in events.iter() {
             num_food += 1;
         }
 
-        let mut rng = rand::thread_rng();
-        for &coord in feasible_food_coord.choose_multiple(&mut rng, num_food) {
+        if num_food == 0 {
+            return;
+        }
+
+        for coord in choose_food_coords(&mut rng.0, board.get_size(), &snake, num_food) {
             commands.add(SpawnFood(coord));
         }
     }
@@ -0,0 +1,286 @@
+use std::io::{Read, Write};
+
+use bevy::prelude::{Color, Component, Commands, Res, ResMut, Resource, Query, Entity, With, DespawnRecursiveExt};
+use rand::rngs::SmallRng;
+
+use arcade_util::{Coord2D, CoordConfiguration, Dir2D, ArcadeError, save_path};
+use crate::board::SnakeBoard;
+use crate::food::{SnakeSeed, FoodRng, FIRST_FOOD, choose_food_coords};
+use crate::snake::{Snake, SnakeTimer};
+use crate::util::{BOARD_SIZE, TICK_DURATION_MS};
+
+const GHOST_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.35);
+
+// File layout: magic, version, board size, tick length in milliseconds, seed, score, number of
+// ticks and then the headings packed four to a byte
+const GHOST_MAGIC: &[u8; 4] = b"SNKG";
+const GHOST_VERSION: u8 = 2;
+const GHOST_HEADER_LEN: usize = 29;
+
+// The rules a run is played by. A ghost only races runs of the same mode, since a run on another
+// board or at another speed can't be followed tick by tick
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GhostMode {
+    board_size: i32,
+    tick_ms: u32,
+}
+
+impl GhostMode {
+    // The mode of the snake game as it is played now
+    pub fn current() -> Self {
+        Self { board_size: BOARD_SIZE, tick_ms: TICK_DURATION_MS.as_millis() as u32 }
+    }
+
+    fn name(&self) -> String {
+        format!("{}x{}_{}ms", self.board_size, self.board_size, self.tick_ms)
+    }
+}
+
+// Recording //
+
+// The heading of the snake at every tick of the current run
+#[derive(Resource, Default)]
+pub struct RunRecording(pub Vec<Dir2D>);
+
+// A finished run that can be replayed without the player that made it
+#[derive(Debug, PartialEq)]
+pub struct GhostRun {
+    mode: GhostMode,
+    seed: u64,
+    score: u32,
+    headings: Vec<Dir2D>,
+}
+
+impl GhostRun {
+    fn file_name(mode: GhostMode, seed: u64) -> String {
+        format!("ghost_{}_{}.bin", mode.name(), seed)
+    }
+
+    // Load the best run for the given mode and seed, if one has been recorded
+    pub fn load(mode: GhostMode, seed: u64) -> Result<Option<GhostRun>, ArcadeError> {
+        let path = save_path("snake", &Self::file_name(mode, seed))?;
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let mut bytes = Vec::new();
+        std::fs::File::open(path)?.read_to_end(&mut bytes)?;
+        Self::decode(&bytes).map(Some)
+    }
+
+    pub fn save(&self) -> Result<(), ArcadeError> {
+        let path = save_path("snake", &Self::file_name(self.mode, self.seed))?;
+        std::fs::File::create(path)?.write_all(&self.encode())?;
+        Ok(())
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(GHOST_HEADER_LEN + self.headings.len() / 4 + 1);
+        bytes.extend_from_slice(GHOST_MAGIC);
+        bytes.push(GHOST_VERSION);
+        bytes.extend_from_slice(&(self.mode.board_size as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.mode.tick_ms.to_le_bytes());
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&self.score.to_le_bytes());
+        bytes.extend_from_slice(&(self.headings.len() as u32).to_le_bytes());
+        for chunk in self.headings.chunks(4) {
+            bytes.push(chunk.iter()
+                .enumerate()
+                .fold(0, |byte, (i, heading)| byte | heading_to_bits(heading) << (2 * i)));
+        }
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> Result<GhostRun, ArcadeError> {
+        let corrupt = || ArcadeError::new("Corrupt snake ghost file");
+        let u32_at = |at: usize| bytes.get(at..at + 4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+            .ok_or_else(corrupt);
+
+        if bytes.get(0..4) != Some(GHOST_MAGIC) || bytes.get(4) != Some(&GHOST_VERSION) {
+            return Err(corrupt());
+        }
+        let mode = GhostMode { board_size: u32_at(5)? as i32, tick_ms: u32_at(9)? };
+        let seed = bytes.get(13..21)
+            .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+            .ok_or_else(corrupt)?;
+        let score = u32_at(21)?;
+        let num_ticks = u32_at(25)? as usize;
+
+        let packed = bytes.get(GHOST_HEADER_LEN..).ok_or_else(corrupt)?;
+        if packed.len() * 4 < num_ticks {
+            return Err(corrupt());
+        }
+        let headings = (0..num_ticks)
+            .map(|tick| bits_to_heading(packed[tick / 4] >> (2 * (tick % 4))))
+            .collect();
+
+        Ok(GhostRun { mode, seed, score, headings })
+    }
+}
+
+fn heading_to_bits(heading: &Dir2D) -> u8 {
+    match heading {
+        Dir2D::Up => 0,
+        Dir2D::Down => 1,
+        Dir2D::Left => 2,
+        Dir2D::Right => 3,
+    }
+}
+
+fn bits_to_heading(bits: u8) -> Dir2D {
+    match bits & 0b11 {
+        0 => Dir2D::Up,
+        1 => Dir2D::Down,
+        2 => Dir2D::Left,
+        _ => Dir2D::Right,
+    }
+}
+
+// Replay //
+
+// The recorded run played back tick by tick, eating its own (invisible) food as it goes
+struct GhostReplay {
+    headings: Vec<Dir2D>,
+    tick: usize,
+    snake: Snake,
+    food: Coord2D<i32>,
+    rng: SmallRng,
+}
+
+impl GhostReplay {
+    fn new(run: GhostRun, seed: &SnakeSeed) -> Self {
+        Self {
+            headings: run.headings,
+            tick: 0,
+            snake: Snake::default(),
+            food: FIRST_FOOD,
+            rng: FoodRng::from_seed(seed).0,
+        }
+    }
+
+    // Step the ghost once, returns false when the recorded run is over
+    fn step_once(&mut self) -> bool {
+        let Some(heading) = self.headings.get(self.tick) else { return false };
+        self.tick += 1;
+
+        self.snake.turn(*heading);
+        self.snake.step_once();
+        if self.snake.get_head() == self.food {
+            self.snake.grow();
+            if let Some(&food) = choose_food_coords(&mut self.rng, BOARD_SIZE, &self.snake, 1).first() {
+                self.food = food;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct SnakeGhost(Option<GhostReplay>);
+
+// Tag component for the translucent sprites of the ghost
+#[derive(Component)]
+pub struct GhostSegment;
+
+// Systems //
+
+pub fn load_ghost(
+    seed: Res<SnakeSeed>,
+    mut ghost: ResMut<SnakeGhost>,
+    mut recording: ResMut<RunRecording>,
+) {
+    recording.0.clear();
+    ghost.0 = match GhostRun::load(GhostMode::current(), seed.0) {
+        Ok(run) => run.map(|run| GhostReplay::new(run, &seed)),
+        Err(e) => {
+            bevy::log::warn!("Could not load snake ghost: {}", e);
+            None
+        },
+    };
+}
+
+// Moves the ghost in lockstep with the snake, so it has to run after the snake timer is ticked
+pub fn move_ghost_forward(
+    mut commands: Commands,
+    timer: Res<SnakeTimer>,
+    mut ghost: ResMut<SnakeGhost>,
+    segments: Query<Entity, With<GhostSegment>>,
+    board: Query<&SnakeBoard>,
+) {
+    if !timer.0.just_finished() {
+        return;
+    }
+    let Some(replay) = ghost.0.as_mut() else { return };
+
+    for entity in &segments {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if !replay.step_once() {
+        ghost.0 = None;
+        return;
+    }
+
+    let board = board.single();
+    for coord in replay.snake.configuration() {
+        commands.spawn(board.tile_sprite_at_coord(coord.0, coord.1, 2, GHOST_COLOR))
+            .insert(GhostSegment);
+    }
+}
+
+// Store the run as the new ghost for this seed if it beats the current best
+pub fn save_if_best_run(seed: &SnakeSeed, recording: &RunRecording, score: u32) {
+    let best = match GhostRun::load(GhostMode::current(), seed.0) {
+        Ok(run) => run.map_or(0, |run| run.score),
+        Err(_) => 0,  // A broken recording can be overwritten
+    };
+    if score <= best {
+        return;
+    }
+
+    let run = GhostRun {
+        mode: GhostMode::current(),
+        seed: seed.0,
+        score,
+        headings: recording.0.clone(),
+    };
+    if let Err(e) = run.save() {
+        bevy::log::warn!("Could not save snake ghost: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(num_ticks: usize) -> GhostRun {
+        let headings = [Dir2D::Up, Dir2D::Right, Dir2D::Down, Dir2D::Left, Dir2D::Right];
+        GhostRun {
+            mode: GhostMode::current(),
+            seed: 0x0123_4567_89ab_cdef,
+            score: 42,
+            headings: headings.iter().copied().cycle().take(num_ticks).collect(),
+        }
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        // Run lengths around the four headings packed in a byte
+        for num_ticks in [0, 1, 3, 4, 5, 8, 1001] {
+            let run = run(num_ticks);
+            assert_eq!(GhostRun::decode(&run.encode()).unwrap(), run);
+        }
+    }
+
+    #[test]
+    fn decode_rejects_corrupt_files() {
+        let bytes = run(10).encode();
+        assert!(GhostRun::decode(&bytes[..GHOST_HEADER_LEN - 1]).is_err());
+        assert!(GhostRun::decode(&bytes[..bytes.len() - 1]).is_err());
+
+        let mut other_version = bytes.clone();
+        other_version[4] = GHOST_VERSION + 1;
+        assert!(GhostRun::decode(&other_version).is_err());
+    }
+}
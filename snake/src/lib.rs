@@ -2,6 +2,9 @@ mod util;
 mod board;
 mod snake;
 mod food;
+mod ghost;
+mod tron;
 mod plugin;
 
-pub use plugin::SnakePlugin;
+pub use plugin::{SnakePlugin, TronPlugin};
+pub use food::SnakeSeed;
@@ -4,13 +4,15 @@ use bevy::prelude::{
     IntoSystemConfigs,
     OnEnter,
     IntoSystemAppConfigs,
+    IntoSystemAppConfig,
     OnExit
 };
 
 use arcade_util::{
     ArcadeState,
     despawn_component,
-    Coord2D
+    Coord2D,
+    TronSettings,
 };
 
 use crate::food::{
@@ -31,6 +33,26 @@ use crate::board::{
     spawn_board,
     SnakeBoard,
 };
+use crate::ghost::{
+    SnakeGhost,
+    RunRecording,
+    GhostSegment,
+    load_ghost,
+    move_ghost_forward,
+};
+use crate::tron::{
+    TronMatch,
+    TronTimer,
+    LightCycles,
+    spawn_cycles,
+    steer_cycles,
+    steer_ai_cycles,
+    move_cycles_forward,
+    tron_round_over,
+    leave_tron,
+    reset_tron_match,
+    reset_cycles,
+};
 
 pub struct SnakePlugin;
 
@@ -40,11 +62,14 @@ impl Plugin for SnakePlugin {
             .add_plugin(FoodPlugin)
             .insert_resource(Snake::default())
             .insert_resource(SnakeTimer::default())
+            .init_resource::<SnakeGhost>()
+            .init_resource::<RunRecording>()
             .add_systems(  // Things to run on starting this game
                 (
                     spawn_board,
                     spawn_snake,
                     spawn_food,
+                    load_ghost,
                 )
                 .chain()
                 .in_schedule(OnEnter(ArcadeState::PlayingSnake)))
@@ -52,6 +77,7 @@ impl Plugin for SnakePlugin {
                 (
                     rotate_snake,
                     move_snake_forward,
+                    move_ghost_forward,
                     snake_eating,
                     snake_game_over,
                 )
@@ -61,9 +87,49 @@ impl Plugin for SnakePlugin {
                 (
                     despawn_component::<SnakeBoard>,
                     despawn_component::<Coord2D<i32>>,
+                    despawn_component::<GhostSegment>,
                     reset_snake,
                 )
                 .chain()
                 .in_schedule(OnExit(ArcadeState::PlayingSnake)));
     }
 }
+
+// Light cycle variant of snake, where the trails never shrink and nothing is eaten
+pub struct TronPlugin;
+
+impl Plugin for TronPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app
+            .init_resource::<TronSettings>()
+            .insert_resource(TronMatch::default())
+            .insert_resource(TronTimer::default())
+            .insert_resource(LightCycles::default())
+            .add_systems(  // Things to run on starting a round
+                (
+                    spawn_board,
+                    spawn_cycles,
+                )
+                .chain()
+                .in_schedule(OnEnter(ArcadeState::PlayingTron)))
+            .add_systems(  // Things to run whilst the round is active
+                (
+                    steer_cycles,
+                    steer_ai_cycles,
+                    move_cycles_forward,
+                    tron_round_over,
+                    leave_tron,
+                )
+                .chain()
+                .in_set(OnUpdate(ArcadeState::PlayingTron)))
+            .add_system(reset_tron_match.in_schedule(OnEnter(ArcadeState::Menu)))
+            .add_systems(  // Things to run on exiting a round
+                (
+                    despawn_component::<SnakeBoard>,
+                    despawn_component::<Coord2D<i32>>,
+                    reset_cycles,
+                )
+                .chain()
+                .in_schedule(OnExit(ArcadeState::PlayingTron)));
+    }
+}
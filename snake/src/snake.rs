@@ -8,7 +8,8 @@ use bevy::prelude::{
 use bevy::time::{Time, Timer, TimerMode};
 
 use arcade_util::{Coord2D, CoordConfiguration, Dir2D, ArcadeState, Collidable};
-use crate::food::{Food, NewFoodEvent};
+use crate::food::{Food, NewFoodEvent, SnakeSeed};
+use crate::ghost::{RunRecording, save_if_best_run};
 use crate::board::SnakeBoard;
 use crate::util::TICK_DURATION_MS;
 
@@ -31,15 +32,20 @@ impl<'a> CoordConfiguration<'a, i32> for Snake {
 
 impl Default for Snake {
     fn default() -> Self {
-        Self {
-            segments: VecDeque::from([Coord2D(1, 1)]),
-            direction: Dir2D::Up,
-            old_tail: Coord2D(1, 0),
-        }
+        Snake::new(Coord2D(1, 1), Dir2D::Up)
     }
 }
 
 impl Snake {
+    // Create a single segment snake at the given coordinate, heading in the given direction
+    pub fn new(head: Coord2D<i32>, direction: Dir2D) -> Self {
+        Self {
+            segments: VecDeque::from([head]),
+            direction,
+            old_tail: head + direction.opposite().as_coord(),
+        }
+    }
+
     pub fn grow(&mut self) {
         self.segments.push_back(self.old_tail);
     }
@@ -51,6 +57,17 @@ impl Snake {
         self.old_tail = self.segments.pop_back().unwrap(); // Remove old tail
     }
 
+    // Change the heading, ignoring attempts to reverse straight into the snake itself
+    pub fn turn(&mut self, direction: Dir2D) {
+        if direction != self.direction.opposite() {
+            self.direction = direction;
+        }
+    }
+
+    pub fn get_direction(&self) -> Dir2D {
+        self.direction
+    }
+
     pub fn get_head(&self) -> Coord2D<i32> {
         self.segments[0]
     }
@@ -67,7 +84,7 @@ pub struct SpawnSnakeSegment(pub Coord2D<i32>);
 impl Command for SpawnSnakeSegment {
     fn write(self, world: &mut World) {
         let board = world.query::<&SnakeBoard>()
-            .iter(&world)
+            .iter(world)
             .next()
             .unwrap();
 
@@ -109,9 +126,11 @@ pub fn move_snake_forward(
     mut timer: ResMut<SnakeTimer>,
     mut commands: Commands,
     mut snake: ResMut<Snake>,
+    mut recording: ResMut<RunRecording>,
     coords: Query<(Entity, &Coord2D<i32>)>,
 ) {
     if timer.0.tick(time.delta()).finished() {
+        recording.0.push(snake.get_direction());
         snake.step_once();
         commands.add(SpawnSnakeSegment(snake.get_head())); // Spawn sprite
 
@@ -159,6 +178,8 @@ pub fn snake_eating(
 pub fn snake_game_over(
     commands: Commands,
     snake: Res<Snake>,
+    seed: Res<SnakeSeed>,
+    recording: Res<RunRecording>,
     query: Query<&SnakeBoard>,
     mut next_state: ResMut<NextState<ArcadeState>>,
 ) {
@@ -169,6 +190,7 @@ pub fn snake_game_over(
         || snake.configuration().skip(1).any(|c| c == snake_head)
         || snake.configuration().count() == (board.get_size()*board.get_size()) as usize
     {
+        save_if_best_run(&seed, &recording, snake.configuration().count() as u32);
         next_state.set(ArcadeState::Popup);
         arcade_popup::spawn_popup(
             commands,
@@ -0,0 +1,298 @@
+use std::collections::HashSet;
+
+use bevy::ecs::system::Command;
+use bevy::prelude::{
+    Color, Resource, Commands, Res, ResMut, Query, World, Input, KeyCode, NextState
+};
+use bevy::time::{Time, Timer, TimerMode};
+
+use arcade_util::{
+    Coord2D,
+    CoordConfiguration,
+    Dir2D,
+    Rot2D,
+    ArcadeState,
+    Collidable,
+    CycleController,
+    TronSettings,
+};
+use crate::board::SnakeBoard;
+use crate::snake::Snake;
+use crate::util::{BOARD_SIZE, TICK_DURATION_MS};
+
+const CYCLE_COLORS: [Color; 4] = [
+    Color::rgb(0.0, 0.8, 0.9),
+    Color::rgb(1.0, 0.5, 0.0),
+    Color::rgb(0.8, 0.1, 0.8),
+    Color::rgb(0.2, 0.7, 0.1),
+];
+
+// Light cycles //
+
+// A light cycle is a snake which never loses its tail
+pub struct LightCycle {
+    snake: Snake,
+    controller: CycleController,
+    color: Color,
+    alive: bool,
+}
+
+impl LightCycle {
+    fn drive(&mut self) {
+        self.snake.step_once();
+        self.snake.grow();
+    }
+}
+
+impl<'a> CoordConfiguration<'a, i32> for LightCycle {
+    fn configuration(&'a self) -> Box<dyn Iterator<Item = &'a Coord2D<i32>> + 'a> {
+        self.snake.configuration()
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct LightCycles(Vec<LightCycle>);
+
+impl LightCycles {
+    // Place the cycles on their starting positions, one on each side of the board
+    fn from_settings(settings: &TronSettings) -> Self {
+        let mid = BOARD_SIZE / 2;
+        let starts = [
+            (Coord2D(2, mid), Dir2D::Right),
+            (Coord2D(BOARD_SIZE - 3, mid - 1), Dir2D::Left),
+            (Coord2D(mid - 1, 2), Dir2D::Up),
+            (Coord2D(mid, BOARD_SIZE - 3), Dir2D::Down),
+        ];
+
+        LightCycles(
+            settings.controllers()
+                .zip(starts)
+                .zip(CYCLE_COLORS)
+                .map(|((controller, (head, direction)), color)| LightCycle {
+                    snake: Snake::new(head, direction),
+                    controller,
+                    color,
+                    alive: true,
+                })
+                .collect()
+        )
+    }
+
+    fn occupied(&self) -> HashSet<Coord2D<i32>> {
+        self.0.iter().flat_map(|cycle| cycle.configuration().copied()).collect()
+    }
+
+    fn alive(&self) -> impl Iterator<Item = (usize, &LightCycle)> {
+        self.0.iter().enumerate().filter(|(_, cycle)| cycle.alive)
+    }
+}
+
+// The score over the rounds of the current match
+#[derive(Resource, Default)]
+pub struct TronMatch {
+    wins: Vec<u32>,
+    round: u32,
+}
+
+impl TronMatch {
+    fn scoreboard(&self) -> String {
+        self.wins.iter()
+            .enumerate()
+            .map(|(i, wins)| format!("P{}: {}", i + 1, wins))
+            .collect::<Vec<_>>()
+            .join("  ")
+    }
+}
+
+// We use the SpawnTrailSegment as a messenger that we need to spawn a colored sprite at the coord
+pub struct SpawnTrailSegment(pub Coord2D<i32>, pub Color);
+
+impl Command for SpawnTrailSegment {
+    fn write(self, world: &mut World) {
+        let board = world.query::<&SnakeBoard>()
+            .iter(world)
+            .next()
+            .unwrap();
+
+        world.spawn(
+            board.tile_sprite_at_coord(self.0.0, self.0.1, 1, self.1)
+            )
+            .insert(self.0);
+    }
+}
+
+// Timer //
+
+#[derive(Resource)]
+pub struct TronTimer(pub Timer);
+
+impl Default for TronTimer {
+    fn default() -> Self {
+        TronTimer(Timer::new(TICK_DURATION_MS, TimerMode::Repeating))
+    }
+}
+
+// Systems //
+
+pub fn spawn_cycles(
+    mut commands: Commands,
+    settings: Res<TronSettings>,
+    mut cycles: ResMut<LightCycles>,
+    mut tron_match: ResMut<TronMatch>,
+) {
+    *cycles = LightCycles::from_settings(&settings);
+    if tron_match.wins.len() != cycles.0.len() {
+        *tron_match = TronMatch { wins: vec![0; cycles.0.len()], round: 0 };
+    }
+    tron_match.round += 1;
+
+    for cycle in cycles.0.iter() {
+        commands.add(SpawnTrailSegment(cycle.snake.get_head(), cycle.color));
+    }
+}
+
+pub fn steer_cycles(
+    input: Res<Input<KeyCode>>,
+    mut cycles: ResMut<LightCycles>,
+) {
+    for cycle in cycles.0.iter_mut().filter(|cycle| cycle.alive) {
+        if let CycleController::Human(scheme) = cycle.controller {
+            let [up, down, left, right] = scheme.keys();
+            if input.pressed(left) {
+                cycle.snake.turn(Dir2D::Left);
+            } else if input.pressed(down) {
+                cycle.snake.turn(Dir2D::Down);
+            } else if input.pressed(up) {
+                cycle.snake.turn(Dir2D::Up);
+            } else if input.pressed(right) {
+                cycle.snake.turn(Dir2D::Right);
+            }
+        }
+    }
+}
+
+// The AI looks straight ahead, left and right, and picks the direction with the longest free run
+pub fn steer_ai_cycles(
+    mut cycles: ResMut<LightCycles>,
+    query: Query<&SnakeBoard>,
+) {
+    let board = query.single();
+    let occupied = cycles.occupied();
+    let free_run = |from: Coord2D<i32>, direction: Dir2D| {
+        std::iter::successors(Some(from + direction.as_coord()), |c| Some(*c + direction.as_coord()))
+            .take_while(|c| !board.collides_with(c) && !occupied.contains(c))
+            .count()
+    };
+
+    for cycle in cycles.0.iter_mut().filter(|c| c.alive && c.controller == CycleController::Ai) {
+        let head = cycle.snake.get_head();
+        let forward = cycle.snake.get_direction();
+        let best = [
+            forward,
+            forward.rotate(&Rot2D::Clockwise),
+            forward.rotate(&Rot2D::CounterClockwise),
+        ]
+        .into_iter()
+        .max_by_key(|&direction| free_run(head, direction))
+        .unwrap();
+
+        // Only leave the straight line if it actually buys more room
+        if free_run(head, best) > free_run(head, forward) {
+            cycle.snake.turn(best);
+        }
+    }
+}
+
+pub fn move_cycles_forward(
+    time: Res<Time>,
+    mut timer: ResMut<TronTimer>,
+    mut commands: Commands,
+    mut cycles: ResMut<LightCycles>,
+    query: Query<&SnakeBoard>,
+) {
+    if !timer.0.tick(time.delta()).finished() {
+        return;
+    }
+
+    let board = query.single();
+    let occupied = cycles.occupied();
+    for cycle in cycles.0.iter_mut().filter(|cycle| cycle.alive) {
+        cycle.drive();
+    }
+
+    // All cycles move at once, so every crash is decided against the same board
+    let heads: Vec<(usize, Coord2D<i32>)> = cycles.alive()
+        .map(|(i, cycle)| (i, cycle.snake.get_head()))
+        .collect();
+    let crashed: Vec<usize> = heads.iter()
+        .filter(|(i, head)| board.collides_with(head)
+            || occupied.contains(head)
+            || heads.iter().any(|(j, other)| i != j && head == other))
+        .map(|(i, _)| *i)
+        .collect();
+
+    for i in crashed {
+        cycles.0[i].alive = false;
+    }
+    for (_, cycle) in cycles.alive() {
+        commands.add(SpawnTrailSegment(cycle.snake.get_head(), cycle.color));
+    }
+}
+
+pub fn tron_round_over(
+    commands: Commands,
+    settings: Res<TronSettings>,
+    cycles: Res<LightCycles>,
+    mut tron_match: ResMut<TronMatch>,
+    mut next_state: ResMut<NextState<ArcadeState>>,
+) {
+    let alive: Vec<usize> = cycles.alive().map(|(i, _)| i).collect();
+    if alive.len() > 1 {
+        return;
+    }
+
+    let header = match alive.first() {
+        Some(&winner) => {
+            tron_match.wins[winner] += 1;
+            format!("P{} takes round {}", winner + 1, tron_match.round)
+        },
+        None => format!("Round {} is a draw", tron_match.round),
+    };
+
+    next_state.set(ArcadeState::Popup);
+    match tron_match.wins.iter().position(|&wins| wins >= settings.wins_needed()) {
+        Some(champion) => {
+            arcade_popup::spawn_popup(
+                commands,
+                format!("P{} wins the match!", champion + 1),
+                tron_match.scoreboard(),
+            );
+        },
+        None => {
+            arcade_popup::spawn_popup_then(
+                commands,
+                header,
+                tron_match.scoreboard(),
+                ArcadeState::PlayingTron,
+            );
+        },
+    }
+}
+
+// Gives up the match and goes back to the menu
+pub fn leave_tron(keys: Res<Input<KeyCode>>, mut next_state: ResMut<NextState<ArcadeState>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(ArcadeState::Menu);
+    }
+}
+
+// A match is over once the menu is shown, whether it was won or left halfway, so the next one
+// starts from zero wins
+pub fn reset_tron_match(mut tron_match: ResMut<TronMatch>) {
+    *tron_match = TronMatch::default();
+}
+
+pub fn reset_cycles(mut cycles: ResMut<LightCycles>, mut timer: ResMut<TronTimer>) {
+    *cycles = LightCycles::default();
+    timer.0.reset();
+}